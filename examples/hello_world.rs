@@ -1,11 +1,18 @@
 use std::fs::write;
 use std::io;
 
-use c_emit::{CArg, Code, VarInit};
+use c_emit::{Braces, CArg, Code, Indent, Style, VarInit};
 
 fn main() -> io::Result<()> {
     let mut code = Code::new();
 
+    code.style(Style {
+        indent: Indent::Spaces(4),
+        braces: Braces::Allman,
+        space_after_comma: true,
+        ..Style::default()
+    });
+
     code.include("stdio.h");
     code.call_func_with_args("printf", vec![CArg::String("Hello World!")]);
     code.new_var("a", VarInit::SizeString(5));
@@ -0,0 +1,152 @@
+//! # Comments.
+
+/// # A comment.
+///
+/// The text may span several lines. It is sanitized so it can never end the
+/// comment early: `*/` is written as `* /` and `/*` as `/ *` in block and
+/// doc comments, and a line comment never ends with a backslash, which would
+/// continue it on the next line.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{Code, Comment, Function};
+///
+/// let mut init = Function::new("init");
+///
+/// init.set_comment(Comment::doc("@brief Set things up.\n\nCall once, */ before anything else."));
+/// init.comment(Comment::line("nothing to do yet"));
+///
+/// let mut code = Code::new();
+///
+/// code.add_func(init);
+/// code.comment(Comment::block("start"));
+/// code.call_func("init");
+///
+/// assert_eq!(code.to_string(), r#"
+/// /**
+///  * @brief Set things up.
+///  *
+///  * Call once, * / before anything else.
+///  */
+/// void init() {
+/// // nothing to do yet
+/// }
+/// int main() {
+/// /* start */
+/// init();
+/// return 0;
+/// }
+/// "#.trim_start().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Comment {
+    /// `// text`, one for each line.
+    Line(String),
+
+    /// `/* text */`.
+    Block(String),
+
+    /// A Doxygen doc comment: `/** text */`.
+    Doc(String),
+}
+
+impl Comment {
+    /// # Make a line comment.
+    pub fn line<S: Into<String>>(text: S) -> Self {
+        Self::Line(text.into())
+    }
+
+    /// # Make a block comment.
+    pub fn block<S: Into<String>>(text: S) -> Self {
+        Self::Block(text.into())
+    }
+
+    /// # Make a Doxygen doc comment.
+    pub fn doc<S: Into<String>>(text: S) -> Self {
+        Self::Doc(text.into())
+    }
+
+    /// # The text of the comment, as given.
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Line(text) | Self::Block(text) | Self::Doc(text) => text,
+        }
+    }
+
+    /// The lines of output for the comment.
+    pub(crate) fn lines(&self) -> Vec<String> {
+        let text = self.text().replace("\r\n", "\n").replace('\r', "\n");
+
+        match self {
+            Self::Line(_) => text
+                .split('\n')
+                .map(|line| {
+                    let mut line = line.trim_end().to_string();
+                    if line.ends_with('\\') || line.ends_with("??/") {
+                        line.push('.');
+                    }
+
+                    if line.is_empty() {
+                        "//".to_string()
+                    } else {
+                        format!("// {line}")
+                    }
+                })
+                .collect(),
+            Self::Block(_) | Self::Doc(_) => {
+                let open = if let Self::Doc(_) = self { "/**" } else { "/*" };
+                let text = text.replace("*/", "* /").replace("/*", "/ *");
+
+                if !text.contains('\n') {
+                    return vec![format!("{open} {text} */")];
+                }
+
+                let mut lines = vec![open.to_string()];
+                for line in text.split('\n') {
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        lines.push(" *".to_string());
+                    } else {
+                        lines.push(format!(" * {line}"));
+                    }
+                }
+                lines.push(" */".to_string());
+
+                lines
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_sanitized() {
+        let comment = Comment::block("a */ b /* c **/");
+
+        assert_eq!(comment.lines(), vec!["/* a * / b / * c ** / */"]);
+    }
+
+    #[test]
+    fn test_line_continuation() {
+        let comment = Comment::line("path C:\\\nnext\r\n\nodd??/");
+
+        assert_eq!(
+            comment.lines(),
+            vec!["// path C:\\.", "// next", "//", "// odd??/."]
+        );
+    }
+
+    #[test]
+    fn test_doc_multiline() {
+        let comment = Comment::doc("@param x the value\n@return */");
+
+        assert_eq!(
+            comment.lines(),
+            vec!["/**", " * @param x the value", " * @return * /", " */"]
+        );
+    }
+}
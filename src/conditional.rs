@@ -0,0 +1,209 @@
+//! # Conditional compilation.
+
+use crate::Error;
+
+/// # The condition opening a conditional compilation region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PpCondition {
+    /// `#if expression`
+    If(String),
+
+    /// `#ifdef NAME`
+    Defined(String),
+
+    /// `#ifndef NAME`
+    NotDefined(String),
+}
+
+/// # A conditional compilation region.
+///
+/// `T` is what the region wraps: a [`Block`](crate::Block) of statements,
+/// or a `Vec` of file-scope [`Item`](crate::Item)s.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{CArg, Code, Include, Item, PpCondition};
+///
+/// let mut code = Code::new();
+///
+/// code.pp_if_items(PpCondition::If("defined(_WIN32)".to_string()), |items| {
+///     items.push(Item::Include(Include::system("windows.h")));
+/// })
+/// .pp_else(|items| {
+///     items.push(Item::Include(Include::system("unistd.h")));
+/// });
+///
+/// code.pp_if(PpCondition::Defined("DEBUG".to_string()), |b| {
+///     b.call_func_with_args("puts", vec![CArg::String("debug build")]);
+/// });
+///
+/// assert_eq!(code.to_string(), r#"
+/// #if defined(_WIN32)
+/// #include<windows.h>
+/// #else
+/// #include<unistd.h>
+/// #endif
+/// int main() {
+/// #ifdef DEBUG
+/// puts("debug build");
+/// #endif
+/// return 0;
+/// }
+/// "#.trim_start().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conditional<T> {
+    /// The opening condition.
+    pub cond: PpCondition,
+
+    /// What is compiled when the opening condition holds.
+    pub then: T,
+
+    /// The `#elif` conditions, and what is compiled when they hold.
+    pub elifs: Vec<(String, T)>,
+
+    /// What is compiled when no condition holds: `#else`.
+    pub otherwise: Option<T>,
+}
+
+impl<T: Default> Conditional<T> {
+    /// # Make a region without `#elif` or `#else` branches.
+    pub fn new(cond: PpCondition, then: T) -> Self {
+        Self {
+            cond,
+            then,
+            elifs: vec![],
+            otherwise: None,
+        }
+    }
+
+    /// # Add an `#elif` branch, built by `build`.
+    pub fn pp_elif<S: Into<String>, F: FnOnce(&mut T)>(&mut self, cond: S, build: F) -> &mut Self {
+        let mut branch = T::default();
+        build(&mut branch);
+
+        self.elifs.push((cond.into(), branch));
+        self
+    }
+
+    /// # Set the `#else` branch, built by `build`.
+    pub fn pp_else<F: FnOnce(&mut T)>(&mut self, build: F) {
+        let mut branch = T::default();
+        build(&mut branch);
+
+        self.otherwise = Some(branch);
+    }
+
+    /// All the branches of the region.
+    pub(crate) fn branches(&self) -> impl Iterator<Item = &T> {
+        std::iter::once(&self.then)
+            .chain(self.elifs.iter().map(|(_, branch)| branch))
+            .chain(&self.otherwise)
+    }
+
+    /// All the branches of the region, for editing.
+    pub(crate) fn branches_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        std::iter::once(&mut self.then)
+            .chain(self.elifs.iter_mut().map(|(_, branch)| branch))
+            .chain(&mut self.otherwise)
+    }
+}
+
+impl PpCondition {
+    /// The directive opening the region.
+    pub(crate) fn directive(&self) -> String {
+        match self {
+            Self::If(expr) => format!("#if {expr}"),
+            Self::Defined(name) => format!("#ifdef {name}"),
+            Self::NotDefined(name) => format!("#ifndef {name}"),
+        }
+    }
+}
+
+/// Check that the conditional compilation directives in `code` are balanced
+/// and that every condition has an argument.
+pub(crate) fn check_balanced(code: &str) -> Result<(), Error> {
+    // Whether each open region has seen its `#else`.
+    let mut open: Vec<bool> = vec![];
+    let mut continued = false;
+
+    for (i, line) in code.lines().enumerate() {
+        let directive = line.trim_start();
+        let is_continuation = continued;
+        continued = line.ends_with('\\');
+
+        if is_continuation || !directive.starts_with('#') {
+            continue;
+        }
+
+        let directive = directive[1..].trim_start();
+        let (name, arg) = directive
+            .split_once(char::is_whitespace)
+            .map(|(name, arg)| (name, arg.trim()))
+            .unwrap_or((directive, ""));
+        let fail = |reason: &str| Err(Error::UnbalancedConditional(i + 1, reason.to_string()));
+
+        match name {
+            "if" | "ifdef" | "ifndef" | "elif" if arg.is_empty() => {
+                return fail(&format!("`#{name}` without a condition"));
+            }
+            "if" | "ifdef" | "ifndef" => open.push(false),
+            "elif" => match open.last() {
+                None => return fail("`#elif` without `#if`"),
+                Some(true) => return fail("`#elif` after `#else`"),
+                Some(false) => {}
+            },
+            "else" => match open.last_mut() {
+                None => return fail("`#else` without `#if`"),
+                Some(true) => return fail("second `#else`"),
+                Some(seen_else) => *seen_else = true,
+            },
+            // The guard closes the innermost region, when there is one.
+            "endif" if open.pop().is_none() => return fail("`#endif` without `#if`"),
+            _ => {}
+        }
+    }
+
+    if open.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::UnbalancedConditional(
+            code.lines().count(),
+            "`#if` without `#endif`".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balanced() {
+        let code = "#if A\n#ifdef B\n#elif C\n#else\n#endif\n#define X \\\n#endif\n#endif\n";
+
+        assert_eq!(check_balanced(code), Ok(()));
+    }
+
+    #[test]
+    fn test_unbalanced() {
+        assert_eq!(
+            check_balanced("#ifdef A\nint x;\n"),
+            Err(Error::UnbalancedConditional(
+                2,
+                "`#if` without `#endif`".to_string()
+            ))
+        );
+        assert_eq!(
+            check_balanced("#endif\n"),
+            Err(Error::UnbalancedConditional(
+                1,
+                "`#endif` without `#if`".to_string()
+            ))
+        );
+        assert!(check_balanced("#if A\n#else\n#elif B\n#endif\n").is_err());
+        assert!(check_balanced("#if A\n#else\n#else\n#endif\n").is_err());
+        assert!(check_balanced("#if\n#endif\n").is_err());
+    }
+}
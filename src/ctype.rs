@@ -0,0 +1,599 @@
+//! # Composable C types.
+
+use std::fmt::{Display, Formatter};
+
+use crate::{Error, Requires, Standard, Style, VarTypes};
+
+/// # A C type.
+///
+/// Types are built up from the base types with [`CType::pointer`],
+/// [`CType::array`], [`CType::function`] and the qualifier methods, and are
+/// rendered with C's declarator syntax, however deeply they are nested.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::CType;
+///
+/// // An array of 4 pointers to functions taking a `char *` and returning `int`.
+/// let fp = CType::function(CType::Int, vec![CType::Char.pointer()]).pointer().array(4);
+///
+/// assert_eq!(fp.declare("fp"), "int (*fp[4])(char *)");
+///
+/// let s = CType::Char.constant().pointer().constant();
+///
+/// assert_eq!(s.declare("s"), "const char *const s");
+/// assert_eq!(s.to_string(), "const char *const");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CType {
+    /// `void`
+    Void,
+
+    /// `bool`
+    Bool,
+
+    /// `char`
+    Char,
+
+    /// `signed char`
+    SignedChar,
+
+    /// `unsigned char`
+    UnsignedChar,
+
+    /// `short`
+    Short,
+
+    /// `unsigned short`
+    UnsignedShort,
+
+    /// `int`
+    Int,
+
+    /// `unsigned int`
+    UnsignedInt,
+
+    /// `long`
+    Long,
+
+    /// `unsigned long`
+    UnsignedLong,
+
+    /// `long long`
+    LongLong,
+
+    /// `unsigned long long`
+    UnsignedLongLong,
+
+    /// `float`
+    Float,
+
+    /// `double`
+    Double,
+
+    /// `long double`
+    LongDouble,
+
+    /// `size_t`
+    SizeT,
+
+    /// `int8_t`
+    Int8,
+
+    /// `int16_t`
+    Int16,
+
+    /// `int32_t`
+    Int32,
+
+    /// `int64_t`
+    Int64,
+
+    /// `uint8_t`
+    UInt8,
+
+    /// `uint16_t`
+    UInt16,
+
+    /// `uint32_t`
+    UInt32,
+
+    /// `uint64_t`
+    UInt64,
+
+    /// `intptr_t`
+    IntPtr,
+
+    /// `uintptr_t`
+    UIntPtr,
+
+    /// A struct, by its name.
+    Struct(String),
+
+    /// A union, by its name.
+    Union(String),
+
+    /// An enum, by its name.
+    Enum(String),
+
+    /// A type introduced by a typedef, by its name.
+    Named(String),
+
+    /// A pointer to a type.
+    Pointer(Box<CType>),
+
+    /// An array of a type, with an optional size.
+    Array(Box<CType>, Option<usize>),
+
+    /// A function type.
+    Function {
+        /// The return type.
+        returns: Box<CType>,
+
+        /// The parameter types.
+        params: Vec<CType>,
+
+        /// Whether the function takes more arguments after `params`: `...`.
+        variadic: bool,
+    },
+
+    /// A qualified type. Qualifying an array qualifies its elements.
+    Qualified(Qualifiers, Box<CType>),
+}
+
+/// # The type qualifiers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Qualifiers {
+    /// `const`
+    pub constant: bool,
+
+    /// `volatile`
+    pub volatile: bool,
+
+    /// `restrict`, for pointers only.
+    pub restrict: bool,
+}
+
+impl Qualifiers {
+    fn merge(self, other: Qualifiers) -> Self {
+        Self {
+            constant: self.constant || other.constant,
+            volatile: self.volatile || other.volatile,
+            restrict: self.restrict || other.restrict,
+        }
+    }
+
+    fn keywords(&self) -> Vec<&'static str> {
+        let mut keywords = vec![];
+
+        if self.constant {
+            keywords.push("const");
+        }
+        if self.volatile {
+            keywords.push("volatile");
+        }
+        if self.restrict {
+            keywords.push("restrict");
+        }
+
+        keywords
+    }
+}
+
+impl CType {
+    /// # A pointer to this type.
+    pub fn pointer(self) -> Self {
+        Self::Pointer(Box::new(self))
+    }
+
+    /// # An array of `size` elements of this type.
+    pub fn array(self, size: usize) -> Self {
+        Self::Array(Box::new(self), Some(size))
+    }
+
+    /// # An array of unspecified size of this type: `[]`.
+    pub fn unsized_array(self) -> Self {
+        Self::Array(Box::new(self), None)
+    }
+
+    /// # A function returning `returns` and taking `params`.
+    pub fn function(returns: CType, params: Vec<CType>) -> Self {
+        Self::Function {
+            returns: Box::new(returns),
+            params,
+            variadic: false,
+        }
+    }
+
+    /// # A function returning `returns`, taking `params` and then any other arguments.
+    ///
+    /// Without `params` the type needs C23.
+    pub fn variadic_function(returns: CType, params: Vec<CType>) -> Self {
+        Self::Function {
+            returns: Box::new(returns),
+            params,
+            variadic: true,
+        }
+    }
+
+    /// # This type with the `const` qualifier.
+    pub fn constant(self) -> Self {
+        self.qualified(Qualifiers {
+            constant: true,
+            ..Qualifiers::default()
+        })
+    }
+
+    /// # This type with the `volatile` qualifier.
+    pub fn volatile(self) -> Self {
+        self.qualified(Qualifiers {
+            volatile: true,
+            ..Qualifiers::default()
+        })
+    }
+
+    /// # This pointer type with the `restrict` qualifier.
+    ///
+    /// Rendering fails with [`Error::RestrictNonPointer`] if this is not
+    /// a pointer, or an array of pointers.
+    pub fn restrict(self) -> Self {
+        self.qualified(Qualifiers {
+            restrict: true,
+            ..Qualifiers::default()
+        })
+    }
+
+    /// # This type with extra qualifiers.
+    pub fn qualified(self, qualifiers: Qualifiers) -> Self {
+        match self {
+            Self::Qualified(existing, inner) => Self::Qualified(existing.merge(qualifiers), inner),
+            ty => Self::Qualified(qualifiers, Box::new(ty)),
+        }
+    }
+
+    /// # The type a value of this type decays to.
+    ///
+    /// Arrays become pointers to their elements and functions become
+    /// function pointers, everything else is unchanged.
+    pub fn decay(&self) -> Self {
+        match self {
+            Self::Array(elem, _) => elem.as_ref().clone().pointer(),
+            Self::Function { .. } => self.clone().pointer(),
+            ty => ty.clone(),
+        }
+    }
+
+    /// # Declare `name` with this type.
+    ///
+    /// An empty `name` gives the abstract type, as used in casts.
+    pub fn declare(&self, name: &str) -> String {
+        self.declare_styled(name, &Style::default())
+    }
+
+    /// Declare `name` with this type, in the given style.
+    pub(crate) fn declare_styled(&self, name: &str, style: &Style) -> String {
+        self.declare_inner(name.to_string(), style)
+    }
+
+    fn declare_inner(&self, inner: String, style: &Style) -> String {
+        match self {
+            Self::Pointer(target) => target.declare_inner(format!("*{inner}"), style),
+            Self::Qualified(qualifiers, target) => match target.as_ref() {
+                Self::Pointer(pointee) => {
+                    let keywords = qualifiers.keywords().join(" ");
+                    let inner = if inner.is_empty() || keywords.is_empty() {
+                        format!("*{keywords}{inner}")
+                    } else {
+                        format!("*{keywords} {inner}")
+                    };
+                    pointee.declare_inner(inner, style)
+                }
+                Self::Array(elem, size) => {
+                    let elem = elem.as_ref().clone().qualified(*qualifiers);
+                    Self::Array(Box::new(elem), *size).declare_inner(inner, style)
+                }
+                Self::Function { .. } => target.declare_inner(inner, style),
+                Self::Qualified(more, base) => {
+                    Self::Qualified(qualifiers.merge(*more), base.clone())
+                        .declare_inner(inner, style)
+                }
+                base => {
+                    let name = base.base_name();
+                    let mut keywords: Vec<&str> = qualifiers.keywords();
+                    keywords.push(&name);
+
+                    join_declarator(keywords.join(" "), inner)
+                }
+            },
+            Self::Array(elem, size) => {
+                let size = size.map(|size| size.to_string()).unwrap_or_default();
+
+                elem.declare_inner(format!("{}[{size}]", parenthesize(inner)), style)
+            }
+            Self::Function {
+                returns,
+                params,
+                variadic,
+            } => {
+                let mut params = params
+                    .iter()
+                    .map(|param| param.declare_styled("", style))
+                    .collect::<Vec<_>>();
+                if *variadic {
+                    params.push("...".to_string());
+                }
+                // `()` declares a function without a prototype before C23.
+                if params.is_empty() {
+                    params.push("void".to_string());
+                }
+
+                returns.declare_inner(
+                    format!("{}({})", parenthesize(inner), params.join(style.comma())),
+                    style,
+                )
+            }
+            base => join_declarator(base.base_name(), inner),
+        }
+    }
+
+    /// The name of a type that is not derived from another one.
+    fn base_name(&self) -> String {
+        match self {
+            Self::Void => "void",
+            Self::Bool => "bool",
+            Self::Char => "char",
+            Self::SignedChar => "signed char",
+            Self::UnsignedChar => "unsigned char",
+            Self::Short => "short",
+            Self::UnsignedShort => "unsigned short",
+            Self::Int => "int",
+            Self::UnsignedInt => "unsigned int",
+            Self::Long => "long",
+            Self::UnsignedLong => "unsigned long",
+            Self::LongLong => "long long",
+            Self::UnsignedLongLong => "unsigned long long",
+            Self::Float => "float",
+            Self::Double => "double",
+            Self::LongDouble => "long double",
+            Self::SizeT => "size_t",
+            Self::Int8 => "int8_t",
+            Self::Int16 => "int16_t",
+            Self::Int32 => "int32_t",
+            Self::Int64 => "int64_t",
+            Self::UInt8 => "uint8_t",
+            Self::UInt16 => "uint16_t",
+            Self::UInt32 => "uint32_t",
+            Self::UInt64 => "uint64_t",
+            Self::IntPtr => "intptr_t",
+            Self::UIntPtr => "uintptr_t",
+            Self::Struct(name) => return format!("struct {name}"),
+            Self::Union(name) => return format!("union {name}"),
+            Self::Enum(name) => return format!("enum {name}"),
+            Self::Named(name) => return name.clone(),
+            Self::Pointer(_) | Self::Array(..) | Self::Function { .. } | Self::Qualified(..) => {
+                unreachable!("derived types have no base name")
+            }
+        }
+        .to_string()
+    }
+
+    /// Whether the type is a pointer, or an array of pointers.
+    fn is_pointer(&self) -> bool {
+        match self {
+            Self::Pointer(_) => true,
+            Self::Array(inner, _) | Self::Qualified(_, inner) => inner.is_pointer(),
+            _ => false,
+        }
+    }
+
+    /// Check that the type can be written in `standard`.
+    pub(crate) fn check(&self, standard: Standard) -> Result<(), Error> {
+        match self {
+            Self::LongLong | Self::UnsignedLongLong => {
+                standard.require(Standard::C99, "`long long`")?
+            }
+            Self::Qualified(qualifiers, inner) if qualifiers.restrict => {
+                standard.require(Standard::C99, "`restrict`")?;
+                if !inner.is_pointer() {
+                    return Err(Error::RestrictNonPointer(self.to_string()));
+                }
+            }
+            Self::Function {
+                params, variadic, ..
+            } if *variadic && params.is_empty() => {
+                standard.require(Standard::C23, "variadic functions without parameters")?
+            }
+            _ => {}
+        }
+
+        match self {
+            Self::Pointer(inner) | Self::Array(inner, _) | Self::Qualified(_, inner) => {
+                inner.check(standard)
+            }
+            Self::Function {
+                returns, params, ..
+            } => std::iter::once(returns.as_ref())
+                .chain(params)
+                .try_for_each(|ty| ty.check(standard)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Collect what the type depends on.
+    pub(crate) fn requires(&self, out: &mut Requires) {
+        match self {
+            Self::Bool => out.header("stdbool.h"),
+            Self::SizeT => out.header("stddef.h"),
+            Self::Int8
+            | Self::Int16
+            | Self::Int32
+            | Self::Int64
+            | Self::UInt8
+            | Self::UInt16
+            | Self::UInt32
+            | Self::UInt64
+            | Self::IntPtr
+            | Self::UIntPtr => out.header("stdint.h"),
+            Self::Struct(name) => out.name(&format!("struct {name}")),
+            Self::Union(name) => out.name(&format!("union {name}")),
+            Self::Enum(name) => out.name(&format!("enum {name}")),
+            Self::Named(name) => out.name(name),
+            Self::Pointer(inner) | Self::Array(inner, _) | Self::Qualified(_, inner) => {
+                inner.requires(out)
+            }
+            Self::Function {
+                returns, params, ..
+            } => {
+                returns.requires(out);
+                for param in params {
+                    param.requires(out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Put a declarator in parentheses if it is a pointer, so that a following
+/// `[]` or `()` applies to the pointer and not the pointee.
+fn parenthesize(inner: String) -> String {
+    if inner.starts_with('*') {
+        format!("({inner})")
+    } else {
+        inner
+    }
+}
+
+/// Join the type specifiers and the declarator.
+fn join_declarator(specifiers: String, inner: String) -> String {
+    if inner.is_empty() {
+        specifiers
+    } else {
+        format!("{specifiers} {inner}")
+    }
+}
+
+impl Display for CType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.declare(""))
+    }
+}
+
+impl From<VarTypes> for CType {
+    /// [`VarTypes::String`] becomes `char[]`, which decays to `char *` where
+    /// an array is not allowed.
+    fn from(ty: VarTypes) -> Self {
+        match ty {
+            VarTypes::String => Self::Char.unsized_array(),
+            VarTypes::Int32 => Self::Int,
+            VarTypes::Int64 => Self::Int64,
+            VarTypes::UInt32 => Self::UnsignedInt,
+            VarTypes::UInt64 => Self::UInt64,
+            VarTypes::Float => Self::Float,
+            VarTypes::Double => Self::Double,
+            VarTypes::Bool => Self::Bool,
+            VarTypes::Char => Self::Char,
+            VarTypes::Struct(name) => Self::Struct(name),
+            VarTypes::Enum(name) => Self::Enum(name),
+            VarTypes::Union(name) => Self::Union(name),
+            VarTypes::Named(name) => Self::Named(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pointer_to_array() {
+        let ty = CType::Int.array(3).pointer();
+
+        assert_eq!(ty.declare("p"), "int (*p)[3]");
+        assert_eq!(ty.to_string(), "int (*)[3]");
+    }
+
+    #[test]
+    fn test_function_returning_pointer() {
+        let ty = CType::function(CType::Char.pointer(), vec![CType::SizeT]);
+
+        assert_eq!(ty.declare("alloc"), "char *alloc(size_t)");
+    }
+
+    #[test]
+    fn test_function_returning_function_pointer() {
+        let handler = CType::function(CType::Void, vec![CType::Int]).pointer();
+        let ty = CType::function(handler, vec![CType::Int, CType::Void.pointer()]);
+
+        assert_eq!(ty.declare("signal"), "void (*signal(int,void *))(int)");
+    }
+
+    #[test]
+    fn test_qualifiers() {
+        let ty = CType::Void.pointer().restrict();
+
+        assert_eq!(ty.declare("dst"), "void *restrict dst");
+
+        let ty = CType::UnsignedLong.volatile().constant();
+
+        assert_eq!(ty.declare("reg"), "const volatile unsigned long reg");
+
+        let ty = CType::Int.array(2).constant();
+
+        assert_eq!(ty.declare("xs"), "const int xs[2]");
+    }
+
+    #[test]
+    fn test_variadic() {
+        let ty = CType::variadic_function(CType::Int, vec![CType::Char.constant().pointer()]);
+
+        assert_eq!(ty.pointer().declare("log"), "int (*log)(const char *,...)");
+    }
+
+    #[test]
+    fn test_variadic_without_params() {
+        let ty = CType::variadic_function(CType::Int, vec![]).pointer();
+
+        assert_eq!(ty.declare("v"), "int (*v)(...)");
+        assert_eq!(
+            ty.check(Standard::C17),
+            Err(Error::Unsupported(
+                "variadic functions without parameters",
+                Standard::C17
+            ))
+        );
+        assert_eq!(ty.check(Standard::C23), Ok(()));
+    }
+
+    #[test]
+    fn test_no_params() {
+        let ty = CType::function(CType::Int, vec![]).pointer();
+
+        assert_eq!(ty.declare("cb"), "int (*cb)(void)");
+    }
+
+    #[test]
+    fn test_restrict_non_pointer() {
+        let ty = CType::Int.restrict();
+
+        assert_eq!(
+            ty.check(Standard::C99),
+            Err(Error::RestrictNonPointer("restrict int".to_string()))
+        );
+        assert_eq!(
+            CType::Int
+                .pointer()
+                .array(2)
+                .restrict()
+                .check(Standard::C99),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_decay() {
+        assert_eq!(CType::Char.array(4).decay(), CType::Char.pointer());
+        assert_eq!(CType::Int.decay(), CType::Int);
+    }
+}
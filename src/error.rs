@@ -0,0 +1,143 @@
+//! # Errors.
+
+use std::fmt::{Display, Formatter};
+
+use crate::Standard;
+
+/// # The errors that can occur while rendering C Code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A `break` statement outside of a loop or switch.
+    BreakOutsideLoop,
+
+    /// A `continue` statement outside of a loop.
+    ContinueOutsideLoop,
+
+    /// The last case of a switch falls through, out of the switch.
+    FallthroughFromLastCase,
+
+    /// A macro uses a parameter it does not have: (macro, parameter).
+    UnknownMacroParam(String, String),
+
+    /// A macro starts or ends with `##`.
+    PasteAtEdge(String),
+
+    /// The conditional compilation directives are not balanced: (line, reason).
+    UnbalancedConditional(usize, String),
+
+    /// A function has two labels with the same name: (function, label).
+    DuplicateLabel(String, String),
+
+    /// A `goto` jumps to a label its function does not have: (function, label).
+    UndefinedLabel(String, String),
+
+    /// A function with deferred cleanup declares the variable holding its
+    /// return value: (function, variable).
+    ReservedName(String, String),
+
+    /// A function with deferred cleanup and a return type returns without a value.
+    ReturnWithoutValue(String),
+
+    /// A character literal is not ASCII, so it does not fit a `char` portably.
+    NonAsciiChar(char),
+
+    /// A struct or union without a name is defined outside of a typedef:
+    /// (`struct` or `union`).
+    AnonymousRecord(&'static str),
+
+    /// An enum without a name asks for a name function, which is named after it.
+    AnonymousEnumNameFn,
+
+    /// A struct or union has no fields, which only GNU C allows: (struct, union or typedef).
+    EmptyRecord(String),
+
+    /// An enum has no variants: (enum).
+    EmptyEnum(String),
+
+    /// A local array has neither a size nor an initializer: (variable).
+    UnsizedArray(String),
+
+    /// The `restrict` qualifier is on a type that is not a pointer: (type).
+    RestrictNonPointer(String),
+
+    /// A project has two files with the same name.
+    DuplicateFile(String),
+
+    /// A project file name is absolute or leaves the output directory with `..`.
+    InvalidFileName(String),
+
+    /// A feature is not available in the C standard: (feature, standard).
+    Unsupported(&'static str, Standard),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BreakOutsideLoop => write!(f, "`break` used outside of a loop or switch"),
+            Self::ContinueOutsideLoop => write!(f, "`continue` used outside of a loop"),
+            Self::FallthroughFromLastCase => {
+                write!(f, "the last case of a switch cannot fall through")
+            }
+            Self::UnknownMacroParam(name, param) => {
+                write!(f, "macro `{name}` has no parameter `{param}`")
+            }
+            Self::PasteAtEdge(name) => {
+                write!(f, "macro `{name}` cannot start or end with `##`")
+            }
+            Self::UnbalancedConditional(line, reason) => {
+                write!(
+                    f,
+                    "unbalanced conditional compilation on line {line}: {reason}"
+                )
+            }
+            Self::DuplicateLabel(func, label) => {
+                write!(f, "function `{func}` has more than one label `{label}`")
+            }
+            Self::UndefinedLabel(func, label) => {
+                write!(f, "function `{func}` has no label `{label}`")
+            }
+            Self::ReservedName(func, name) => {
+                write!(
+                    f,
+                    "function `{func}` declares `{name}`, which its deferred cleanup uses"
+                )
+            }
+            Self::ReturnWithoutValue(func) => {
+                write!(
+                    f,
+                    "function `{func}` returns without a value before its cleanup"
+                )
+            }
+            Self::NonAsciiChar(c) => {
+                write!(f, "`{c}` is not ASCII, it cannot be a character literal")
+            }
+            Self::AnonymousRecord(keyword) => {
+                write!(
+                    f,
+                    "a {keyword} without a name can only be defined in a typedef"
+                )
+            }
+            Self::AnonymousEnumNameFn => {
+                write!(f, "an enum without a name cannot have a name function")
+            }
+            Self::EmptyRecord(name) => write!(f, "`{name}` has no fields"),
+            Self::EmptyEnum(name) => write!(f, "enum `{name}` has no variants"),
+            Self::UnsizedArray(name) => {
+                write!(f, "array `{name}` needs a size or an initializer")
+            }
+            Self::RestrictNonPointer(ty) => {
+                write!(f, "`{ty}` is not a pointer, it cannot be `restrict`")
+            }
+            Self::DuplicateFile(name) => write!(f, "the project has more than one `{name}`"),
+            Self::InvalidFileName(name) => {
+                write!(f, "`{name}` is not a relative path inside the project")
+            }
+            Self::Unsupported(feature, standard) => {
+                write!(f, "{feature} is not available in {standard}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,247 @@
+//! # Spelling of C literals.
+
+/// The C string literal for `s`, quotes included.
+///
+/// Only printable ASCII is written as is. Control characters are written as
+/// their named escape, or as a three digit octal escape which cannot take
+/// the digits after it. Other characters are written as the hex escapes of
+/// their UTF-8 bytes; a hex escape takes every hex digit after it, so the
+/// literal is split (`"\xc3\xa9" "e"`) before a following hex digit.
+/// A `?` after a `?` is escaped, so no trigraph can appear.
+pub(crate) fn string_literal(s: &str) -> String {
+    let mut out = String::from("\"");
+    let mut after_hex = false;
+    let mut after_question = false;
+
+    for c in s.chars() {
+        if after_hex && c.is_ascii_hexdigit() {
+            out.push_str("\" \"");
+        }
+        after_hex = false;
+
+        match c {
+            '"' => out.push_str("\\\""),
+            '?' if after_question => out.push_str("\\?"),
+            c if c.is_ascii() => push_ascii(&mut out, c),
+            c => {
+                let mut bytes = [0; 4];
+                for byte in c.encode_utf8(&mut bytes).bytes() {
+                    out.push_str(&format!("\\x{byte:02x}"));
+                }
+                after_hex = true;
+            }
+        }
+
+        after_question = c == '?';
+    }
+
+    out.push('"');
+    out
+}
+
+/// The C character literal for `c`, quotes included.
+///
+/// Characters outside ASCII do not fit a `char` portably, rendering C Code
+/// with them fails with [`Error::NonAsciiChar`](crate::Error::NonAsciiChar).
+/// Where nothing can fail, they are written as `'?'`.
+pub(crate) fn char_literal(c: char) -> String {
+    match c {
+        '\'' => "'\\''".to_string(),
+        c if c.is_ascii() => {
+            let mut out = String::from("'");
+            push_ascii(&mut out, c);
+            out.push('\'');
+            out
+        }
+        _ => "'?'".to_string(),
+    }
+}
+
+/// The C literal for the float `n`, with the `f` suffix.
+///
+/// Decimal literals have the fewest digits that read back as `n`, and always
+/// a `.` or an exponent so they are not read as integers. Hex literals
+/// (`0x1.8p+1f`) are exact by construction. Infinities and NaNs have no
+/// literal and are written as the `INFINITY` and `NAN` macros of `math.h`.
+pub(crate) fn float_literal(n: f32, hex: bool) -> String {
+    if let Some(special) = special_float(n.into()) {
+        special.to_string()
+    } else if hex {
+        format!("{}f", hex_float(n.into()))
+    } else {
+        format!("{n:?}f")
+    }
+}
+
+/// The C literal for the double `n`, as for [`float_literal`] but without
+/// a suffix.
+pub(crate) fn double_literal(n: f64, hex: bool) -> String {
+    if let Some(special) = special_float(n) {
+        special.to_string()
+    } else if hex {
+        hex_float(n)
+    } else {
+        format!("{n:?}")
+    }
+}
+
+/// The `math.h` macro for a value without a literal.
+fn special_float(n: f64) -> Option<&'static str> {
+    if n.is_nan() {
+        Some("NAN")
+    } else if n == f64::INFINITY {
+        Some("INFINITY")
+    } else if n == f64::NEG_INFINITY {
+        Some("-INFINITY")
+    } else {
+        None
+    }
+}
+
+/// The hexadecimal spelling of a finite `n`, as `printf("%a")` writes it.
+///
+/// A float converts to a double exactly and its mantissa bits come first,
+/// so this spells floats too.
+fn hex_float(n: f64) -> String {
+    let sign = if n.is_sign_negative() { "-" } else { "" };
+    let bits = n.abs().to_bits();
+    let exponent = (bits >> 52) as i64;
+    let mantissa = bits & ((1 << 52) - 1);
+
+    if exponent == 0 && mantissa == 0 {
+        return format!("{sign}0x0p+0");
+    }
+
+    // Subnormals have no implicit leading 1 and the minimum exponent.
+    let (lead, exponent) = if exponent == 0 {
+        (0, -1022)
+    } else {
+        (1, exponent - 1023)
+    };
+    let digits = format!("{mantissa:013x}");
+    let digits = digits.trim_end_matches('0');
+
+    if digits.is_empty() {
+        format!("{sign}0x{lead}p{exponent:+}")
+    } else {
+        format!("{sign}0x{lead}.{digits}p{exponent:+}")
+    }
+}
+
+/// Write an ASCII character, escaped if it is a backslash or not printable.
+fn push_ascii(out: &mut String, c: char) {
+    match c {
+        '\\' => out.push_str("\\\\"),
+        '\n' => out.push_str("\\n"),
+        '\t' => out.push_str("\\t"),
+        '\r' => out.push_str("\\r"),
+        '\x07' => out.push_str("\\a"),
+        '\x08' => out.push_str("\\b"),
+        '\x0b' => out.push_str("\\v"),
+        '\x0c' => out.push_str("\\f"),
+        c if c.is_ascii_control() => out.push_str(&format!("\\{:03o}", c as u32)),
+        c => out.push(c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain() {
+        assert_eq!(string_literal("Hello, world!"), r#""Hello, world!""#);
+    }
+
+    #[test]
+    fn test_named_escapes() {
+        assert_eq!(
+            string_literal("a\"b\\c\r\n\t\x07\x08\x0b\x0c'"),
+            r#""a\"b\\c\r\n\t\a\b\v\f'""#
+        );
+    }
+
+    #[test]
+    fn test_octal_escapes() {
+        assert_eq!(string_literal("\0"), r#""\000""#);
+        assert_eq!(string_literal("\x001"), r#""\0001""#);
+        assert_eq!(string_literal("\x1b[0m\x7f"), r#""\033[0m\177""#);
+    }
+
+    #[test]
+    fn test_hex_continuation() {
+        assert_eq!(string_literal("é"), r#""\xc3\xa9""#);
+        assert_eq!(string_literal("éB"), r#""\xc3\xa9" "B""#);
+        assert_eq!(string_literal("éx"), r#""\xc3\xa9x""#);
+        assert_eq!(string_literal("é1€"), r#""\xc3\xa9" "1\xe2\x82\xac""#);
+    }
+
+    #[test]
+    fn test_char_literals() {
+        assert_eq!(char_literal('a'), "'a'");
+        assert_eq!(char_literal('\''), r"'\''");
+        assert_eq!(char_literal('"'), "'\"'");
+        assert_eq!(char_literal('\\'), r"'\\'");
+        assert_eq!(char_literal('\n'), r"'\n'");
+        assert_eq!(char_literal('\0'), r"'\000'");
+        assert_eq!(char_literal('?'), "'?'");
+        assert_eq!(char_literal('é'), "'?'");
+        assert_eq!(char_literal('😀'), "'?'");
+    }
+
+    #[test]
+    fn test_trigraphs() {
+        assert_eq!(string_literal("what??!"), r#""what?\?!""#);
+        assert_eq!(string_literal("???="), r#""?\?\?=""#);
+        assert_eq!(string_literal("? ?"), r#""? ?""#);
+    }
+
+    #[test]
+    fn test_float_literals() {
+        assert_eq!(float_literal(1.0, false), "1.0f");
+        assert_eq!(float_literal(0.1, false), "0.1f");
+        assert_eq!(float_literal(-2.5, false), "-2.5f");
+        assert_eq!(float_literal(f32::MAX, false), "3.4028235e38f");
+        assert_eq!(float_literal(1e-7, false), "1e-7f");
+        assert_eq!(double_literal(1.0, false), "1.0");
+        assert_eq!(double_literal(0.1, false), "0.1");
+        assert_eq!(double_literal(f64::MAX, false), "1.7976931348623157e308");
+        assert_eq!(double_literal(-0.0, false), "-0.0");
+    }
+
+    #[test]
+    fn test_float_round_trip() {
+        for n in [
+            0.1f32,
+            1.0 / 3.0,
+            f32::MIN_POSITIVE,
+            f32::EPSILON,
+            16777217.0,
+        ] {
+            let literal = float_literal(n, false);
+            assert_eq!(literal.trim_end_matches('f').parse::<f32>(), Ok(n));
+        }
+        for n in [0.1, 1.0 / 3.0, f64::MIN_POSITIVE, 5e-324, 1e23] {
+            assert_eq!(double_literal(n, false).parse::<f64>(), Ok(n));
+        }
+    }
+
+    #[test]
+    fn test_hex_floats() {
+        assert_eq!(float_literal(3.0, true), "0x1.8p+1f");
+        assert_eq!(float_literal(0.1, true), "0x1.99999ap-4f");
+        assert_eq!(float_literal(1e-45, true), "0x1p-149f");
+        assert_eq!(double_literal(1.0, true), "0x1p+0");
+        assert_eq!(double_literal(-0.0, true), "-0x0p+0");
+        assert_eq!(double_literal(0.1, true), "0x1.999999999999ap-4");
+        assert_eq!(double_literal(5e-324, true), "0x0.0000000000001p-1022");
+    }
+
+    #[test]
+    fn test_special_floats() {
+        assert_eq!(float_literal(f32::INFINITY, false), "INFINITY");
+        assert_eq!(float_literal(f32::NEG_INFINITY, true), "-INFINITY");
+        assert_eq!(double_literal(f64::NAN, false), "NAN");
+        assert_eq!(double_literal(-f64::NAN, true), "NAN");
+    }
+}
@@ -0,0 +1,782 @@
+//! # C Expressions.
+
+use std::fmt::{Display, Formatter};
+
+use crate::{CArg, CType, Error, Requires, Standard, Style};
+
+/// # The binary operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    /// `*`
+    Mul,
+
+    /// `/`
+    Div,
+
+    /// `%`
+    Rem,
+
+    /// `+`
+    Add,
+
+    /// `-`
+    Sub,
+
+    /// `<<`
+    Shl,
+
+    /// `>>`
+    Shr,
+
+    /// `<`
+    Lt,
+
+    /// `<=`
+    Le,
+
+    /// `>`
+    Gt,
+
+    /// `>=`
+    Ge,
+
+    /// `==`
+    Eq,
+
+    /// `!=`
+    Ne,
+
+    /// `&`
+    BitAnd,
+
+    /// `^`
+    BitXor,
+
+    /// `|`
+    BitOr,
+
+    /// `&&`
+    And,
+
+    /// `||`
+    Or,
+}
+
+/// # The assignment operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignOp {
+    /// `=`
+    Assign,
+
+    /// `+=`
+    Add,
+
+    /// `-=`
+    Sub,
+
+    /// `*=`
+    Mul,
+
+    /// `/=`
+    Div,
+
+    /// `%=`
+    Rem,
+
+    /// `<<=`
+    Shl,
+
+    /// `>>=`
+    Shr,
+
+    /// `&=`
+    BitAnd,
+
+    /// `^=`
+    BitXor,
+
+    /// `|=`
+    BitOr,
+}
+
+/// # The unary operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnOp {
+    /// `-x`
+    Neg,
+
+    /// `+x`
+    Plus,
+
+    /// `!x`
+    Not,
+
+    /// `~x`
+    BitNot,
+
+    /// `&x`
+    AddrOf,
+
+    /// `*x`
+    Deref,
+
+    /// `++x`
+    PreInc,
+
+    /// `--x`
+    PreDec,
+
+    /// `x++`
+    PostInc,
+
+    /// `x--`
+    PostDec,
+}
+
+/// # A C Expression.
+///
+/// Expressions are rendered with the minimum amount of parentheses needed to
+/// keep the tree's meaning under C's precedence rules.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{BinOp, Expr};
+///
+/// let sum = Expr::binary(BinOp::Add, Expr::ident("a"), Expr::Int32(1));
+/// let expr = Expr::binary(BinOp::Mul, sum, Expr::ident("b"));
+///
+/// assert_eq!(expr.to_string(), "(a + 1) * b");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// A string literal, of any text: it is escaped as needed.
+    String(String),
+
+    /// An identifier.
+    Ident(String),
+
+    /// An i32 literal.
+    Int32(i32),
+
+    /// An i64 literal, with the `LL` suffix.
+    Int64(i64),
+
+    /// A u32 literal, with the `U` suffix.
+    UInt32(u32),
+
+    /// A u64 literal, with the `ULL` suffix.
+    UInt64(u64),
+
+    /// A float literal, with the `f` suffix. Infinities and NaNs are
+    /// written as `INFINITY` and `NAN`, which include `math.h`.
+    Float(f32),
+
+    /// A 'double' literal, written like [`Expr::Float`] without the suffix.
+    Double(f64),
+
+    /// A boolean literal.
+    Bool(bool),
+
+    /// A character literal, escaped as needed.
+    ///
+    /// A `char` only holds the characters of the basic execution set
+    /// portably, so only ASCII characters can be rendered: others make
+    /// [`Code::render`](crate::Code::render) fail with
+    /// [`Error::NonAsciiChar`], and they display as `'?'`. Use a string
+    /// literal for other text.
+    Char(char),
+
+    /// A binary operation.
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+
+    /// A unary operation.
+    Unary(UnOp, Box<Expr>),
+
+    /// An assignment: `target=value`, `target+=value`, ...
+    Assign(AssignOp, Box<Expr>, Box<Expr>),
+
+    /// A function call.
+    Call(Box<Expr>, Vec<Expr>),
+
+    /// An array subscript: `a[i]`.
+    Index(Box<Expr>, Box<Expr>),
+
+    /// A member access: `s.field`.
+    Member(Box<Expr>, String),
+
+    /// A member access through a pointer: `p->field`.
+    Arrow(Box<Expr>, String),
+
+    /// A cast: `(type)x`, or a compound literal of an [`Expr::InitList`].
+    Cast(CType, Box<Expr>),
+
+    /// A brace-enclosed initializer list, with optional designators: `{.x=1,2}`.
+    ///
+    /// Cast it to a struct type to get a compound literal.
+    InitList(Vec<(Option<String>, Expr)>),
+}
+
+impl Expr {
+    /// # Make an identifier.
+    pub fn ident<S: Into<String>>(name: S) -> Self {
+        Self::Ident(name.into())
+    }
+
+    /// # Make a string literal.
+    pub fn string<S: Into<String>>(s: S) -> Self {
+        Self::String(s.into())
+    }
+
+    /// # Make a binary operation.
+    pub fn binary(op: BinOp, lhs: Expr, rhs: Expr) -> Self {
+        Self::Binary(op, Box::new(lhs), Box::new(rhs))
+    }
+
+    /// # Make a unary operation.
+    pub fn unary(op: UnOp, operand: Expr) -> Self {
+        Self::Unary(op, Box::new(operand))
+    }
+
+    /// # Assign a value to a variable, array element, member or dereferenced pointer.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{AssignOp, Expr};
+    ///
+    /// let expr = Expr::assign(Expr::deref(Expr::ident("p")), Expr::Int32(5));
+    ///
+    /// assert_eq!(expr.to_string(), "*p=5");
+    ///
+    /// let expr = Expr::assign_op(AssignOp::Shl, Expr::index(Expr::ident("a"), Expr::Int32(0)), Expr::Int32(2));
+    ///
+    /// assert_eq!(expr.to_string(), "a[0]<<=2");
+    /// ```
+    pub fn assign(target: Expr, value: Expr) -> Self {
+        Self::assign_op(AssignOp::Assign, target, value)
+    }
+
+    /// # Assign with a compound operator: `target+=value`, ...
+    pub fn assign_op(op: AssignOp, target: Expr, value: Expr) -> Self {
+        Self::Assign(op, Box::new(target), Box::new(value))
+    }
+
+    /// # Call a function by name.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::Expr;
+    ///
+    /// let expr = Expr::call("strlen", vec![Expr::ident("x")]);
+    ///
+    /// assert_eq!(expr.to_string(), "strlen(x)");
+    /// ```
+    pub fn call<S: Into<String>>(func: S, args: Vec<Expr>) -> Self {
+        Self::Call(Box::new(Self::ident(func)), args)
+    }
+
+    /// # Call a function by name, with arguments like [`Block::call_func_with_args`].
+    ///
+    /// [`Block::call_func_with_args`]: crate::Block::call_func_with_args
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{CArg, Expr};
+    ///
+    /// let expr = Expr::call_with_args("fopen", vec![CArg::Ident("path"), CArg::String("r")]);
+    ///
+    /// assert_eq!(expr.to_string(), "fopen(path,\"r\")");
+    /// ```
+    pub fn call_with_args<S: Into<String>, A: Into<Expr>>(func: S, args: Vec<A>) -> Self {
+        Self::call(func, args.into_iter().map(Into::into).collect())
+    }
+
+    /// # Index into an array: `array[index]`.
+    pub fn index(array: Expr, index: Expr) -> Self {
+        Self::Index(Box::new(array), Box::new(index))
+    }
+
+    /// # Access a member of a struct: `object.field`.
+    pub fn member<S: Into<String>>(object: Expr, field: S) -> Self {
+        Self::Member(Box::new(object), field.into())
+    }
+
+    /// # Access a member through a pointer: `pointer->field`.
+    pub fn arrow<S: Into<String>>(pointer: Expr, field: S) -> Self {
+        Self::Arrow(Box::new(pointer), field.into())
+    }
+
+    /// # Take the address of an expression: `&x`.
+    pub fn addr_of(operand: Expr) -> Self {
+        Self::unary(UnOp::AddrOf, operand)
+    }
+
+    /// # Dereference a pointer: `*x`.
+    pub fn deref(operand: Expr) -> Self {
+        Self::unary(UnOp::Deref, operand)
+    }
+
+    /// # Cast an expression to another type: `(type)x`.
+    pub fn cast<T: Into<CType>>(ty: T, operand: Expr) -> Self {
+        Self::Cast(ty.into(), Box::new(operand))
+    }
+
+    /// # Render the expression in the given style.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{Expr, Style};
+    ///
+    /// let style = Style { space_after_comma: true, space_around_assign: true, ..Style::default() };
+    /// let expr = Expr::assign(Expr::ident("n"), Expr::call("max", vec![Expr::ident("a"), Expr::ident("b")]));
+    ///
+    /// assert_eq!(expr.render(&style), "n = max(a, b)");
+    /// assert_eq!(expr.to_string(), "n=max(a,b)");
+    /// ```
+    pub fn render(&self, style: &Style) -> String {
+        Styled(self, style).to_string()
+    }
+
+    /// Collect what this expression depends on.
+    pub(crate) fn requires(&self, out: &mut Requires) {
+        match self {
+            Self::Ident(name) => out.name(name),
+            Self::Bool(_) => out.header("stdbool.h"),
+            Self::Float(n) if !n.is_finite() => out.header("math.h"),
+            Self::Double(n) if !n.is_finite() => out.header("math.h"),
+            Self::Cast(ty, _) => ty.requires(out),
+            _ => {}
+        }
+
+        for child in self.children() {
+            child.requires(out);
+        }
+    }
+
+    /// Check that the expression can be written in C, in `standard` and `style`.
+    pub(crate) fn check(&self, standard: Standard, style: &Style) -> Result<(), Error> {
+        match self {
+            Self::Char(c) if !c.is_ascii() => return Err(Error::NonAsciiChar(*c)),
+            Self::Int64(_) | Self::UInt64(_) => {
+                standard.require(Standard::C99, "`long long` literals")?
+            }
+            Self::Float(n) if !n.is_finite() => {
+                standard.require(Standard::C99, "`INFINITY` and `NAN`")?
+            }
+            Self::Double(n) if !n.is_finite() => {
+                standard.require(Standard::C99, "`INFINITY` and `NAN`")?
+            }
+            Self::Float(_) | Self::Double(_) if style.hex_floats => {
+                standard.require(Standard::C99, "hexadecimal floats")?
+            }
+            Self::InitList(values) if values.iter().any(|(field, _)| field.is_some()) => {
+                standard.require(Standard::C99, "designated initializers")?
+            }
+            Self::Cast(ty, value) => {
+                ty.check(standard)?;
+                if let Self::InitList(_) = value.as_ref() {
+                    standard.require(Standard::C99, "compound literals")?
+                }
+            }
+            _ => {}
+        }
+
+        self.children()
+            .into_iter()
+            .try_for_each(|child| child.check(standard, style))
+    }
+
+    /// The expressions this expression is made of.
+    fn children(&self) -> Vec<&Expr> {
+        match self {
+            Self::Binary(_, lhs, rhs) | Self::Assign(_, lhs, rhs) | Self::Index(lhs, rhs) => {
+                vec![lhs, rhs]
+            }
+            Self::Unary(_, operand)
+            | Self::Member(operand, _)
+            | Self::Arrow(operand, _)
+            | Self::Cast(_, operand) => vec![operand],
+            Self::Call(func, args) => std::iter::once(func.as_ref()).chain(args).collect(),
+            Self::InitList(values) => values.iter().map(|(_, value)| value).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// The precedence of the expression, higher binds tighter.
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Binary(op, ..) => op.precedence(),
+            Self::Assign(..) => 2,
+            Self::Unary(op, _) if op.is_postfix() => 15,
+            Self::Unary(..) | Self::Cast(..) => 14,
+            // The minimum values are written as a subtraction, see `fmt_prec`.
+            Self::Int32(i32::MIN) | Self::Int64(i64::MIN) => 12,
+            Self::Int32(n) if *n < 0 => 14,
+            Self::Int64(n) if *n < 0 => 14,
+            Self::Float(n) if n.is_sign_negative() && !n.is_nan() => 14,
+            Self::Double(n) if n.is_sign_negative() && !n.is_nan() => 14,
+            _ => 16,
+        }
+    }
+
+    /// Whether the rendered expression starts with a sign that would merge
+    /// with a preceding prefix operator (`- -x` must not become `--x`).
+    fn starts_with_prefix_op(&self) -> bool {
+        match self {
+            Self::Unary(op, _) => !op.is_postfix(),
+            _ => self.precedence() == 14 && !matches!(self, Self::Cast(..)),
+        }
+    }
+
+    fn fmt_prec(&self, f: &mut Formatter<'_>, min: u8, style: &Style) -> std::fmt::Result {
+        if self.precedence() < min {
+            write!(f, "(")?;
+            self.fmt_prec(f, 0, style)?;
+            return write!(f, ")");
+        }
+
+        match self {
+            Self::String(s) => write!(f, "{}", crate::escape::string_literal(s)),
+            Self::Ident(id) => write!(f, "{id}"),
+            // `-2147483648` negates `2147483648`, which does not fit an `int`.
+            Self::Int32(i32::MIN) => write!(f, "{} - 1", i32::MIN + 1),
+            Self::Int32(n) => write!(f, "{n}"),
+            Self::Int64(i64::MIN) => write!(f, "{}LL - 1", i64::MIN + 1),
+            Self::Int64(n) => write!(f, "{n}LL"),
+            Self::UInt32(n) => write!(f, "{n}U"),
+            Self::UInt64(n) => write!(f, "{n}ULL"),
+            Self::Float(n) => write!(f, "{}", crate::escape::float_literal(*n, style.hex_floats)),
+            Self::Double(n) => write!(f, "{}", crate::escape::double_literal(*n, style.hex_floats)),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Char(c) => write!(f, "{}", crate::escape::char_literal(*c)),
+            Self::Binary(op, lhs, rhs) => {
+                let prec = op.precedence();
+
+                lhs.fmt_prec(f, prec, style)?;
+                write!(f, " {} ", op.symbol())?;
+                rhs.fmt_prec(f, prec + 1, style)
+            }
+            // Assignment is right associative: `a=b=c` is `a=(b=c)`.
+            Self::Assign(op, target, value) => {
+                target.fmt_prec(f, 14, style)?;
+                write!(f, "{}", style.assign(*op))?;
+                value.fmt_prec(f, 2, style)
+            }
+            Self::Unary(op, operand) if op.is_postfix() => {
+                operand.fmt_prec(f, 15, style)?;
+                write!(f, "{}", op.symbol())
+            }
+            Self::Unary(op, operand) => {
+                write!(f, "{}", op.symbol())?;
+
+                if operand.starts_with_prefix_op() {
+                    write!(f, "(")?;
+                    operand.fmt_prec(f, 0, style)?;
+                    write!(f, ")")
+                } else {
+                    operand.fmt_prec(f, 14, style)
+                }
+            }
+            Self::Call(func, args) => {
+                func.fmt_prec(f, 15, style)?;
+                write!(f, "(")?;
+
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{}", style.comma())?;
+                    }
+                    arg.fmt_prec(f, 0, style)?;
+                }
+
+                write!(f, ")")
+            }
+            Self::Index(array, index) => {
+                array.fmt_prec(f, 15, style)?;
+                write!(f, "[")?;
+                index.fmt_prec(f, 0, style)?;
+                write!(f, "]")
+            }
+            Self::Member(object, field) => {
+                object.fmt_prec(f, 15, style)?;
+                write!(f, ".{field}")
+            }
+            Self::Arrow(pointer, field) => {
+                pointer.fmt_prec(f, 15, style)?;
+                write!(f, "->{field}")
+            }
+            Self::Cast(ty, operand) => {
+                // Only compound literals can have array types, casts cannot.
+                if let Self::InitList(_) = operand.as_ref() {
+                    write!(f, "({})", ty.declare_styled("", style))?;
+                } else {
+                    write!(f, "({})", ty.decay().declare_styled("", style))?;
+                }
+                operand.fmt_prec(f, 14, style)
+            }
+            Self::InitList(values) => {
+                write!(f, "{{")?;
+
+                for (i, (field, value)) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{}", style.comma())?;
+                    }
+                    if let Some(field) = field {
+                        write!(f, ".{field}{}", style.assign(AssignOp::Assign))?;
+                    }
+                    value.fmt_prec(f, 0, style)?;
+                }
+
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+impl BinOp {
+    /// The C spelling of the operator.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Shl => "<<",
+            Self::Shr => ">>",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::BitAnd => "&",
+            Self::BitXor => "^",
+            Self::BitOr => "|",
+            Self::And => "&&",
+            Self::Or => "||",
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Mul | Self::Div | Self::Rem => 13,
+            Self::Add | Self::Sub => 12,
+            Self::Shl | Self::Shr => 11,
+            Self::Lt | Self::Le | Self::Gt | Self::Ge => 10,
+            Self::Eq | Self::Ne => 9,
+            Self::BitAnd => 8,
+            Self::BitXor => 7,
+            Self::BitOr => 6,
+            Self::And => 5,
+            Self::Or => 4,
+        }
+    }
+}
+
+impl AssignOp {
+    /// The C spelling of the operator.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Assign => "=",
+            Self::Add => "+=",
+            Self::Sub => "-=",
+            Self::Mul => "*=",
+            Self::Div => "/=",
+            Self::Rem => "%=",
+            Self::Shl => "<<=",
+            Self::Shr => ">>=",
+            Self::BitAnd => "&=",
+            Self::BitXor => "^=",
+            Self::BitOr => "|=",
+        }
+    }
+}
+
+impl UnOp {
+    /// The C spelling of the operator.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Neg => "-",
+            Self::Plus => "+",
+            Self::Not => "!",
+            Self::BitNot => "~",
+            Self::AddrOf => "&",
+            Self::Deref => "*",
+            Self::PreInc | Self::PostInc => "++",
+            Self::PreDec | Self::PostDec => "--",
+        }
+    }
+
+    /// Whether the operator is written after its operand.
+    pub fn is_postfix(&self) -> bool {
+        matches!(self, Self::PostInc | Self::PostDec)
+    }
+}
+
+impl Display for Expr {
+    /// Renders in the default [`Style`].
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.fmt_prec(f, 0, &Style::default())
+    }
+}
+
+/// An expression rendered in a given style.
+struct Styled<'a>(&'a Expr, &'a Style);
+
+impl Display for Styled<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt_prec(f, 0, self.1)
+    }
+}
+
+impl From<CArg<'_>> for Expr {
+    fn from(arg: CArg<'_>) -> Self {
+        match arg {
+            CArg::String(s) => Self::String(s.to_string()),
+            CArg::Ident(id) => Self::Ident(id.to_string()),
+            CArg::Int32(n) => Self::Int32(n),
+            CArg::Int64(n) => Self::Int64(n),
+            CArg::UInt32(n) => Self::UInt32(n),
+            CArg::UInt64(n) => Self::UInt64(n),
+            CArg::Float(n) => Self::Float(n),
+            CArg::Double(n) => Self::Double(n),
+            CArg::Bool(b) => Self::Bool(b),
+            CArg::Char(c) => Self::Char(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ident(name: &str) -> Expr {
+        Expr::ident(name)
+    }
+
+    #[test]
+    fn test_left_assoc() {
+        let expr = Expr::binary(
+            BinOp::Sub,
+            Expr::binary(BinOp::Sub, ident("a"), ident("b")),
+            ident("c"),
+        );
+
+        assert_eq!(expr.to_string(), "a - b - c");
+    }
+
+    #[test]
+    fn test_right_nested_same_prec() {
+        let expr = Expr::binary(
+            BinOp::Sub,
+            ident("a"),
+            Expr::binary(BinOp::Sub, ident("b"), ident("c")),
+        );
+
+        assert_eq!(expr.to_string(), "a - (b - c)");
+    }
+
+    #[test]
+    fn test_lower_prec_child() {
+        let expr = Expr::binary(
+            BinOp::And,
+            Expr::binary(BinOp::Or, ident("a"), ident("b")),
+            Expr::binary(BinOp::Lt, ident("c"), Expr::Int32(1)),
+        );
+
+        assert_eq!(expr.to_string(), "(a || b) && c < 1");
+    }
+
+    #[test]
+    fn test_postfix_operand() {
+        let expr = Expr::member(Expr::deref(ident("p")), "x");
+
+        assert_eq!(expr.to_string(), "(*p).x");
+
+        let expr = Expr::index(Expr::arrow(ident("s"), "buf"), ident("i"));
+
+        assert_eq!(expr.to_string(), "s->buf[i]");
+    }
+
+    #[test]
+    fn test_nested_call() {
+        let expr = Expr::call(
+            "printf",
+            vec![
+                Expr::string("%d"),
+                Expr::call("strlen", vec![Expr::addr_of(ident("buf"))]),
+            ],
+        );
+
+        assert_eq!(expr.to_string(), "printf(\"%d\",strlen(&buf))");
+    }
+
+    #[test]
+    fn test_cast() {
+        let expr = Expr::cast(
+            CType::Double,
+            Expr::binary(BinOp::Add, ident("a"), ident("b")),
+        );
+
+        assert_eq!(expr.to_string(), "(double)(a + b)");
+    }
+
+    #[test]
+    fn test_no_token_merging() {
+        let expr = Expr::unary(UnOp::Neg, Expr::unary(UnOp::Neg, ident("x")));
+
+        assert_eq!(expr.to_string(), "-(-x)");
+
+        let expr = Expr::binary(BinOp::Sub, ident("a"), Expr::Int32(-1));
+
+        assert_eq!(expr.to_string(), "a - -1");
+
+        let expr = Expr::unary(UnOp::Neg, Expr::Int32(-1));
+
+        assert_eq!(expr.to_string(), "-(-1)");
+    }
+
+    #[test]
+    fn test_postfix_inc() {
+        let expr = Expr::unary(UnOp::PostInc, Expr::deref(ident("p")));
+
+        assert_eq!(expr.to_string(), "(*p)++");
+    }
+
+    #[test]
+    fn test_assign() {
+        let expr = Expr::assign(
+            ident("a"),
+            Expr::assign(
+                ident("b"),
+                Expr::binary(BinOp::Add, ident("c"), Expr::Int32(1)),
+            ),
+        );
+
+        assert_eq!(expr.to_string(), "a=b=c + 1");
+
+        let expr = Expr::assign_op(
+            AssignOp::BitOr,
+            Expr::arrow(ident("s"), "flags"),
+            Expr::assign(ident("f"), ident("g")),
+        );
+
+        assert_eq!(expr.to_string(), "s->flags|=f=g");
+
+        let expr = Expr::assign(Expr::assign(ident("a"), ident("b")), ident("c"));
+
+        assert_eq!(expr.to_string(), "(a=b)=c");
+
+        let expr = Expr::binary(
+            BinOp::Ne,
+            Expr::assign(ident("c"), Expr::call("getchar", vec![])),
+            ident("EOF"),
+        );
+
+        assert_eq!(expr.to_string(), "(c=getchar()) != EOF");
+    }
+}
@@ -0,0 +1,206 @@
+//! # C Functions.
+
+use std::ops::{Deref, DerefMut};
+
+use crate::{Block, CType, Comment, Requires};
+
+/// # A C Function definition.
+///
+/// The body is a [`Block`], and all of its methods can be called on the
+/// `Function` directly.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{BinOp, Code, Expr, Function, VarTypes};
+///
+/// let mut add = Function::new("add");
+///
+/// add.returns(VarTypes::Int32);
+/// add.param(VarTypes::Int32, "a");
+/// add.param(VarTypes::Int32, "b");
+/// add.ret(Expr::binary(BinOp::Add, Expr::ident("a"), Expr::ident("b")));
+///
+/// let mut code = Code::new();
+///
+/// code.add_func(add);
+///
+/// assert_eq!(code.to_string(), r#"
+/// int add(int a,int b) {
+/// return a + b;
+/// }
+/// int main() {
+/// return 0;
+/// }
+/// "#.trim_start().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    name: String,
+    returns: CType,
+    params: Vec<Param>,
+    body: Block,
+    deferred: Vec<Block>,
+    comment: Option<Comment>,
+}
+
+/// # A function parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    /// The type of the parameter.
+    pub ty: CType,
+
+    /// The name of the parameter.
+    pub name: String,
+}
+
+impl Function {
+    /// # Create a new function returning `void`, without parameters.
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            returns: CType::Void,
+            params: vec![],
+            body: Block::new(),
+            deferred: vec![],
+            comment: None,
+        }
+    }
+
+    /// # Set the return type of the function.
+    pub fn returns<T: Into<CType>>(&mut self, ty: T) {
+        self.returns = ty.into();
+    }
+
+    /// # Add a parameter to the function.
+    pub fn param<T: Into<CType>, S: Into<String>>(&mut self, ty: T, name: S) {
+        self.params.push(Param {
+            ty: ty.into(),
+            name: name.into(),
+        });
+    }
+
+    /// # Attach a comment, written right before the function.
+    ///
+    /// Use [`Block::comment`] for a comment inside the body.
+    pub fn set_comment(&mut self, comment: Comment) {
+        self.comment = Some(comment);
+    }
+
+    /// # The comment attached to the function.
+    pub fn attached_comment(&self) -> Option<&Comment> {
+        self.comment.as_ref()
+    }
+
+    /// # The name of the function.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// # The return type of the function.
+    pub fn return_type(&self) -> &CType {
+        &self.returns
+    }
+
+    /// # The parameters of the function.
+    pub fn params(&self) -> &[Param] {
+        &self.params
+    }
+
+    /// # The body of the function.
+    pub fn body(&self) -> &Block {
+        &self.body
+    }
+
+    /// # Add a cleanup action, built by `build`, to run whenever the function returns.
+    ///
+    /// The cleanup actions run in the reverse order they were added, after
+    /// a single `cleanup:` label at the end of the function. Every return
+    /// becomes a jump there, storing its value in a `result` variable that
+    /// is returned after the cleanup. All the actions run on every return,
+    /// so they must be safe to run before their resource is acquired.
+    ///
+    /// Rendering fails with [`Error::ReservedName`](crate::Error::ReservedName)
+    /// if a function with a return type declares `result` itself, and with
+    /// [`Error::ReturnWithoutValue`](crate::Error::ReturnWithoutValue) if it
+    /// returns without a value.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{CArg, CType, Code, Expr, Function, UnOp, VarInit};
+    ///
+    /// let mut load = Function::new("load");
+    ///
+    /// load.returns(CType::Int);
+    /// load.new_var("buf", VarInit::Typed(CType::Char.pointer(), Expr::call_with_args("malloc", vec![CArg::Int32(64)])));
+    /// load.defer(|b| b.call_func_with_args("free", vec![CArg::Ident("buf")]));
+    /// load.if_(Expr::unary(UnOp::Not, Expr::ident("buf")), |b| b.ret(Expr::Int32(-1)));
+    /// load.call_func_with_args("fill", vec![CArg::Ident("buf")]);
+    /// load.ret(Expr::Int32(0));
+    ///
+    /// let mut code = Code::library();
+    ///
+    /// code.include("stdlib.h");
+    /// code.add_func(load);
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// #include<stdlib.h>
+    /// int load() {
+    /// int result;
+    /// char *buf=malloc(64);
+    /// if (!buf) {
+    /// result=-1;
+    /// goto cleanup;
+    /// }
+    /// fill(buf);
+    /// result=0;
+    /// cleanup:
+    /// free(buf);
+    /// return result;
+    /// }
+    /// "#.trim_start().to_string());
+    /// ```
+    pub fn defer<F: FnOnce(&mut Block)>(&mut self, build: F) {
+        let mut action = Block::new();
+        build(&mut action);
+
+        self.deferred.push(action);
+    }
+
+    /// # The cleanup actions, in the order they were added.
+    pub fn deferred(&self) -> &[Block] {
+        &self.deferred
+    }
+
+    /// Collect what the function depends on.
+    pub(crate) fn requires(&self, out: &mut Requires) {
+        self.signature_requires(out);
+        self.body.requires(out);
+        for action in &self.deferred {
+            action.requires(out);
+        }
+    }
+
+    /// Collect what the declaration of the function depends on.
+    pub(crate) fn signature_requires(&self, out: &mut Requires) {
+        self.returns.requires(out);
+        for param in &self.params {
+            param.ty.requires(out);
+        }
+    }
+}
+
+impl Deref for Function {
+    type Target = Block;
+
+    fn deref(&self) -> &Block {
+        &self.body
+    }
+}
+
+impl DerefMut for Function {
+    fn deref_mut(&mut self) -> &mut Block {
+        &mut self.body
+    }
+}
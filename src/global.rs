@@ -0,0 +1,107 @@
+//! # File-scope variables.
+
+use crate::{Comment, Decl};
+
+/// # A file-scope variable.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{CType, Code, Decl, Expr, Global, Storage, VarInit};
+///
+/// let mut count = Global::new(Decl::new("count", VarInit::Int32(0)));
+/// count.storage(Storage::Static);
+///
+/// let mut errno_copy = Global::new(Decl::typed(CType::Int, "last_error", None));
+/// errno_copy.storage(Storage::Extern);
+/// errno_copy.thread_local();
+///
+/// let mut code = Code::new();
+///
+/// code.add_global(count);
+/// code.add_global(errno_copy);
+/// code.call_func("tick");
+///
+/// assert_eq!(code.to_string(), r#"
+/// static int count=0;
+/// extern _Thread_local int last_error;
+/// int main() {
+/// tick();
+/// return 0;
+/// }
+/// "#.trim_start().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Global {
+    decl: Decl,
+    storage: Option<Storage>,
+    thread_local: bool,
+    comment: Option<Comment>,
+}
+
+/// # The storage-class specifiers of a file-scope variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Storage {
+    /// `static`, only visible in this translation unit.
+    Static,
+
+    /// `extern`, defined in another translation unit.
+    Extern,
+}
+
+impl Global {
+    /// # Make a file-scope variable out of a declaration.
+    pub fn new(decl: Decl) -> Self {
+        Self {
+            decl,
+            storage: None,
+            thread_local: false,
+            comment: None,
+        }
+    }
+
+    /// # Set the storage class of the variable.
+    pub fn storage(&mut self, storage: Storage) {
+        self.storage = Some(storage);
+    }
+
+    /// # Give every thread its own copy of the variable: `_Thread_local`.
+    pub fn thread_local(&mut self) {
+        self.thread_local = true;
+    }
+
+    /// # Attach a comment, written right before the variable.
+    pub fn set_comment(&mut self, comment: Comment) {
+        self.comment = Some(comment);
+    }
+
+    /// # The comment attached to the variable.
+    pub fn attached_comment(&self) -> Option<&Comment> {
+        self.comment.as_ref()
+    }
+
+    /// # The declaration of the variable.
+    pub fn decl(&self) -> &Decl {
+        &self.decl
+    }
+
+    /// # The storage class of the variable.
+    pub fn storage_class(&self) -> Option<Storage> {
+        self.storage
+    }
+
+    /// # Whether the variable is `_Thread_local`.
+    pub fn is_thread_local(&self) -> bool {
+        self.thread_local
+    }
+}
+
+impl Storage {
+    /// The C spelling of the storage class.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Self::Static => "static",
+            Self::Extern => "extern",
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//! # Includes.
+
+/// # An `#include` directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Include {
+    /// A system header: `#include<file>`.
+    System(String),
+
+    /// A local header, searched next to the source first: `#include "file"`.
+    Local(String),
+}
+
+impl Include {
+    /// # Include a system header.
+    pub fn system<S: Into<String>>(file: S) -> Self {
+        Self::System(file.into())
+    }
+
+    /// # Include a local header.
+    pub fn local<S: Into<String>>(file: S) -> Self {
+        Self::Local(file.into())
+    }
+
+    /// # The included file.
+    pub fn file(&self) -> &str {
+        match self {
+            Self::System(file) | Self::Local(file) => file,
+        }
+    }
+
+    /// The `#include` line.
+    pub(crate) fn directive(&self) -> String {
+        match self {
+            Self::System(file) => format!("#include<{file}>"),
+            Self::Local(file) => format!("#include \"{file}\""),
+        }
+    }
+}
@@ -22,7 +22,42 @@
 
 #![deny(missing_docs)]
 
+mod comment;
+mod conditional;
+mod ctype;
+mod error;
+mod escape;
+mod expr;
+mod func;
+mod global;
+mod include;
+mod macros;
+mod printer;
+mod project;
+mod standard;
+mod stmt;
+mod style;
+mod types;
+
 use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+
+use printer::Printer;
+
+pub use comment::Comment;
+pub use conditional::{Conditional, PpCondition};
+pub use ctype::{CType, Qualifiers};
+pub use error::Error;
+pub use expr::{AssignOp, BinOp, Expr, UnOp};
+pub use func::{Function, Param};
+pub use global::{Global, Storage};
+pub use include::Include;
+pub use macros::{Macro, MacroPiece};
+pub use project::{Guard, Header, Project};
+pub use standard::Standard;
+pub use stmt::{Block, Case, CaseEnd, Decl, For, ForInit, If, Stmt, Switch};
+pub use style::{Braces, Indent, Style};
+pub use types::{Enum, Field, Struct, Typedef, TypedefTarget, Union, Variant};
 
 /// # The Code Struct.
 ///
@@ -41,10 +76,141 @@
 /// }
 /// "#.trim_start().to_string());
 /// ```
-pub struct Code<'a> {
-    code: String,
-    requires: Vec<&'a str>,
+///
+/// The statements of `main` live in a [`Block`], and all of its methods
+/// can be called on `Code` directly.
+pub struct Code {
+    items: Vec<Item>,
+    main: Function,
+    has_main: bool,
+    requires: Vec<Include>,
+    include_comments: Vec<(Include, Comment)>,
     exit: i32,
+    style: Style,
+    standard: Standard,
+}
+
+/// # A file-scope item of the C Code.
+///
+/// Functions are emitted after all other items, so they can use any of them.
+/// The functions of a conditional region are emitted in a second region
+/// with the same condition, so its other items stay in place.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    /// A function definition.
+    Function(Function),
+
+    /// A function declaration, by the signature of a function; its body is not emitted.
+    Prototype(Function),
+
+    /// A struct definition.
+    Struct(Struct),
+
+    /// An enum definition.
+    Enum(Enum),
+
+    /// A union definition.
+    Union(Union),
+
+    /// A typedef.
+    Typedef(Typedef),
+
+    /// A file-scope variable.
+    Global(Global),
+
+    /// A `#define`.
+    Macro(Macro),
+
+    /// A compile-time assertion of a condition, with a message.
+    StaticAssert(Expr, String),
+
+    /// An `#undef` of a macro, by its name.
+    Undef(String),
+
+    /// An `#include`, for use in conditional regions.
+    Include(Include),
+
+    /// A conditional compilation region.
+    Conditional(Conditional<Vec<Item>>),
+
+    /// A comment, emitted with the item after it.
+    Comment(Comment),
+}
+
+impl Item {
+    /// Collect what the item depends on.
+    pub(crate) fn requires(&self, out: &mut Requires) {
+        match self {
+            Self::Function(func) => func.requires(out),
+            Self::Prototype(func) => func.signature_requires(out),
+            Self::Struct(def) => def.requires(out),
+            Self::Union(def) => def.requires(out),
+            Self::Typedef(def) => def.requires(out),
+            Self::Global(global) => global.decl().requires(out),
+            Self::StaticAssert(cond, _) => cond.requires(out),
+            Self::Conditional(region) => {
+                for item in region.branches().flatten() {
+                    item.requires(out);
+                }
+            }
+            Self::Enum(_)
+            | Self::Macro(_)
+            | Self::Undef(_)
+            | Self::Include(_)
+            | Self::Comment(_) => {}
+        }
+    }
+
+    /// Collect the names the item defines or declares, named as in [`Requires`].
+    pub(crate) fn defines(&self, out: &mut Vec<String>) {
+        let mut tag = |keyword: &str, name: &str| {
+            if !name.is_empty() {
+                out.push(format!("{keyword} {name}"));
+            }
+        };
+
+        match self {
+            Self::Function(func) | Self::Prototype(func) => out.push(func.name().to_string()),
+            Self::Struct(def) => tag("struct", def.name()),
+            Self::Union(def) => tag("union", def.name()),
+            Self::Enum(def) => {
+                tag("enum", def.name());
+                out.extend(def.variants().iter().map(|variant| variant.name.clone()));
+                if def.has_name_fn() {
+                    out.push(format!("{}_name", def.name()));
+                }
+            }
+            Self::Typedef(def) => {
+                match def.target() {
+                    TypedefTarget::Struct(inner) => tag("struct", inner.name()),
+                    TypedefTarget::Union(inner) => tag("union", inner.name()),
+                    TypedefTarget::Type(_) => {}
+                }
+                out.push(def.name().to_string());
+            }
+            Self::Global(global) => out.push(global.decl().name.clone()),
+            Self::Macro(def) => out.push(def.name().to_string()),
+            Self::Conditional(region) => {
+                for item in region.branches().flatten() {
+                    item.defines(out);
+                }
+            }
+            Self::StaticAssert(..) | Self::Undef(_) | Self::Include(_) | Self::Comment(_) => {}
+        }
+    }
+
+    /// The comment attached to the item.
+    pub(crate) fn attached_comment(&self) -> Option<&Comment> {
+        match self {
+            Self::Function(func) | Self::Prototype(func) => func.attached_comment(),
+            Self::Struct(def) => def.attached_comment(),
+            Self::Enum(def) => def.attached_comment(),
+            Self::Union(def) => def.attached_comment(),
+            Self::Typedef(def) => def.attached_comment(),
+            Self::Global(global) => global.attached_comment(),
+            _ => None,
+        }
+    }
 }
 
 /// # The C Argument.
@@ -62,6 +228,12 @@ pub enum CArg<'a> {
     /// The i64 argument.
     Int64(i64),
 
+    /// The u32 argument.
+    UInt32(u32),
+
+    /// The u64 argument.
+    UInt64(u64),
+
     /// The float argument.
     Float(f32),
 
@@ -76,7 +248,9 @@ pub enum CArg<'a> {
 }
 
 /// # The variable types.
-#[derive(Debug, Clone, Copy)]
+///
+/// These are the common types, see [`CType`] for all of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum VarTypes {
     /// String.
     String,
@@ -84,9 +258,15 @@ pub enum VarTypes {
     /// i32.
     Int32,
 
-    /// i64.
+    /// i64: `int64_t`.
     Int64,
 
+    /// u32: `unsigned int`.
+    UInt32,
+
+    /// u64: `uint64_t`.
+    UInt64,
+
     /// Float.
     Float,
 
@@ -98,10 +278,22 @@ pub enum VarTypes {
 
     /// Character.
     Char,
+
+    /// A struct, by its name.
+    Struct(String),
+
+    /// An enum, by its name.
+    Enum(String),
+
+    /// A union, by its name.
+    Union(String),
+
+    /// A type introduced by a typedef, by its name.
+    Named(String),
 }
 
 /// # The variable initialization.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum VarInit<'a> {
     /// Initialize a string.
     String(&'a str),
@@ -112,9 +304,15 @@ pub enum VarInit<'a> {
     /// Initialize an i32.
     Int32(i32),
 
-    /// Initialize an i64.
+    /// Initialize an `int64_t`.
     Int64(i64),
 
+    /// Initialize an `unsigned int`.
+    UInt32(u32),
+
+    /// Initialize a `uint64_t`.
+    UInt64(u64),
+
     /// Initialize a float.
     Float(f32),
 
@@ -129,15 +327,30 @@ pub enum VarInit<'a> {
 
     /// **(FOR STRINGS ONLY!)** Set the variable to uninitialized with a specific size.
     SizeString(usize),
+
+    /// Initialize a variable of the given type with any expression.
+    Expr(VarTypes, Expr),
+
+    /// Initialize a variable of any type, such as a pointer, with any expression.
+    Typed(CType, Expr),
+
+    /// Initialize a struct, by its name, with designated initializers: `{.x=1}`.
+    Struct(&'a str, Vec<(&'a str, Expr)>),
+
+    /// Leave a variable of the given type uninitialized.
+    ///
+    /// A `VarTypes::String` has no size, rendering a local one fails with
+    /// [`Error::UnsizedArray`]; use [`VarInit::SizeString`] for a buffer.
+    Uninit(VarTypes),
 }
 
-impl Default for Code<'_> {
+impl Default for Code {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl Code<'_> {
+impl Code {
     /// # Create a new C Code object.
     ///
     /// ## Example
@@ -153,10 +366,48 @@ impl Code<'_> {
     /// "#.trim_start().to_string());
     /// ```
     pub fn new() -> Self {
+        let mut main = Function::new("main");
+        main.returns(VarTypes::Int32);
+
         Self {
-            code: String::new(),
+            items: vec![],
+            main,
+            has_main: true,
             requires: vec![],
+            include_comments: vec![],
             exit: 0,
+            style: Style::default(),
+            standard: Standard::default(),
+        }
+    }
+
+    /// # Create new C Code without a `main` function, for a library.
+    ///
+    /// Statements added to the `main` of a library are not emitted.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{Code, Expr, Function, VarTypes};
+    ///
+    /// let mut answer = Function::new("answer");
+    /// answer.returns(VarTypes::Int32);
+    /// answer.ret(Expr::Int32(42));
+    ///
+    /// let mut code = Code::library();
+    ///
+    /// code.add_func(answer);
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// int answer() {
+    /// return 42;
+    /// }
+    /// "#.trim_start().to_string());
+    /// ```
+    pub fn library() -> Self {
+        Self {
+            has_main: false,
+            ..Self::new()
         }
     }
 
@@ -181,6 +432,16 @@ pub fn exit(&mut self, code: i32) {
         self.exit = code;
     }
 
+    /// # Set how the C Code is laid out, see [`Style`].
+    pub fn style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    /// # Set the C standard the C Code is written for, see [`Standard`].
+    pub fn standard(&mut self, standard: Standard) {
+        self.standard = standard;
+    }
+
     /// # #include < any file into the C Code. >
     ///
     /// ## Example
@@ -199,14 +460,11 @@ pub fn exit(&mut self, code: i32) {
     /// }
     /// "#.trim_start().to_string());
     /// ```
-    pub fn include(&mut self, file: &'static str) {
-        if self.requires.contains(&file) {
-            return;
-        }
-        self.requires.push(file);
+    pub fn include<S: Into<String>>(&mut self, file: S) {
+        self.add_include(Include::System(file.into()));
     }
 
-    /// # Call a function WITHOUT arguments.
+    /// # #include " a local file into the C Code. "
     ///
     /// ## Example
     ///
@@ -214,223 +472,288 @@ pub fn include(&mut self, file: &'static str) {
     /// use c_emit::Code;
     ///
     /// let mut code = Code::new();
+    /// let module = "parser";
     ///
-    /// code.call_func("printf");
+    /// code.include("stdio.h");
+    /// code.include_local(format!("{module}.h"));
+    /// code.include_local("parser.h");
     ///
     /// assert_eq!(code.to_string(), r#"
+    /// #include<stdio.h>
+    /// #include "parser.h"
     /// int main() {
-    /// printf();
     /// return 0;
     /// }
     /// "#.trim_start().to_string());
     /// ```
-    pub fn call_func(&mut self, func: &str) {
-        self.code.push_str(func);
-        self.code.push_str("();\n")
+    pub fn include_local<S: Into<String>>(&mut self, file: S) {
+        self.add_include(Include::Local(file.into()));
+    }
+
+    /// # Add an include, unless the same file is already included the same way.
+    pub fn add_include(&mut self, include: Include) {
+        if self.requires.contains(&include) {
+            return;
+        }
+        self.requires.push(include);
     }
 
-    /// # Call a function WITH arguments.
+    /// # Add an include with a comment written right before it.
     ///
     /// ## Example
     ///
     /// ```rust
-    /// use c_emit::{Code, CArg};
+    /// use c_emit::{Code, Comment, Include};
     ///
     /// let mut code = Code::new();
     ///
-    /// code.call_func_with_args("printf", vec![CArg::String("Hello, world!")]);
+    /// code.comment_include(Include::system("stdio.h"), Comment::line("for printf"));
     ///
     /// assert_eq!(code.to_string(), r#"
+    /// // for printf
+    /// #include<stdio.h>
     /// int main() {
-    /// printf("Hello, world!");
     /// return 0;
     /// }
     /// "#.trim_start().to_string());
     /// ```
-    pub fn call_func_with_args(&mut self, func: &str, args: Vec<CArg>) {
-        self.code.push_str(func);
-        self.code.push('(');
-
-        for arg in args {
-            match arg {
-                CArg::String(s) => {
-                    let s = s.replace("\r\n", "\\r\\n");
-                    let s = s.replace('\n', "\\n");
-                    let s = s.replace('\t', "\\t");
-                    let s = s.replace('"', "\\\"");
-
-                    self.code.push('"');
-                    self.code.push_str(s.as_str());
-                    self.code.push('"');
-                }
-                CArg::Ident(id) => {
-                    self.code.push_str(id);
-                }
-                CArg::Int32(n) => {
-                    self.code.push_str(&n.to_string());
-                }
-                CArg::Int64(n) => {
-                    self.code.push_str(&n.to_string());
-                }
-                CArg::Float(n) => {
-                    self.code.push_str(&n.to_string());
-                }
-                CArg::Double(n) => {
-                    self.code.push_str(&n.to_string());
-                }
-                CArg::Bool(b) => {
-                    self.code.push_str(&b.to_string());
-                }
-                CArg::Char(c) => {
-                    self.code.push(c);
-                }
-            }
-            self.code.push(',');
-        }
-
-        if self.code.ends_with(',') {
-            self.code = self.code.strip_suffix(',').unwrap().to_string();
-        }
+    pub fn comment_include(&mut self, include: Include, comment: Comment) {
+        self.include_comments.push((include.clone(), comment));
+        self.add_include(include);
+    }
 
-        self.code.push_str(");\n")
+    /// # The includes, in the order they are emitted.
+    pub fn includes(&self) -> &[Include] {
+        &self.requires
     }
 
-    /// # Make a new variable.
+    /// # Define a function before `main`, after all other items.
     ///
     /// ## Example
     ///
     /// ```rust
-    /// use c_emit::{Code, CArg, VarInit};
+    /// use c_emit::{Code, CArg, Function};
+    ///
+    /// let mut greet = Function::new("greet");
+    /// greet.call_func_with_args("puts", vec![CArg::String("Hi!")]);
     ///
     /// let mut code = Code::new();
     ///
-    /// code.new_var("a", VarInit::String("hello"));
+    /// code.include("stdio.h");
+    /// code.add_func(greet);
+    /// code.call_func("greet");
     ///
     /// assert_eq!(code.to_string(), r#"
+    /// #include<stdio.h>
+    /// void greet() {
+    /// puts("Hi!");
+    /// }
     /// int main() {
-    /// char a[]="hello";
+    /// greet();
     /// return 0;
     /// }
     /// "#.trim_start().to_string());
-    ///
     /// ```
-    /// ## NOTE:
-    /// Set the `initval` argument to `None` to make the variable uninitialized.
-    pub fn new_var<S: AsRef<str>>(&mut self, name: S, value: VarInit) {
-        let name = name.as_ref();
-
-        match value {
-            VarInit::String(s) => {
-                self.code.push_str("char ");
-                self.code.push_str(name);
-
-                self.code.push_str("[]=\"");
-                self.code.push_str(s);
-                self.code.push_str("\";");
-                self.code.push('\n');
-            }
-            VarInit::Ident(ty, ident) => {
-                self.code.push_str(match ty {
-                    VarTypes::String => "char ",
-                    VarTypes::Int32 => "int ",
-                    VarTypes::Int64 => "int ",
-                    VarTypes::Float => "float ",
-                    VarTypes::Double => "double ",
-                    VarTypes::Bool => {
-                        self.requires.push("stdbool.h");
-                        "bool "
-                    }
-                    VarTypes::Char => "char ",
-                });
+    pub fn add_func(&mut self, func: Function) {
+        self.items.push(Item::Function(func));
+    }
 
-                self.code.push_str(name);
+    /// # The file-scope items, in the order they are emitted.
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
 
-                if let VarTypes::String = ty {
-                    self.code.push_str("[]");
-                }
+    /// # The file-scope items, for editing.
+    pub fn items_mut(&mut self) -> &mut Vec<Item> {
+        &mut self.items
+    }
 
-                self.code.push('=');
-                self.code.push_str(ident);
-                self.code.push(';');
-                self.code.push('\n');
-            }
-            VarInit::Bool(b) => {
-                self.requires.push("stdbool.h");
+    /// # Define a struct before the functions that use it.
+    pub fn add_struct(&mut self, def: Struct) {
+        self.items.push(Item::Struct(def));
+    }
 
-                self.code.push_str("bool ");
-                self.code.push_str(name);
+    /// # Define an enum before the functions that use it.
+    pub fn add_enum(&mut self, def: Enum) {
+        self.items.push(Item::Enum(def));
+    }
 
-                self.code.push('=');
-                self.code.push_str(&b.to_string());
-                self.code.push_str(";\n");
-            }
-            VarInit::Char(c) => {
-                self.code.push_str("char ");
-                self.code.push_str(name);
+    /// # Define a union before the functions that use it.
+    pub fn add_union(&mut self, def: Union) {
+        self.items.push(Item::Union(def));
+    }
 
-                self.code.push_str("='");
-                self.code.push(c);
-                self.code.push_str("';\n");
-            }
-            VarInit::Double(f) => {
-                self.code.push_str("double ");
-                self.code.push_str(name);
+    /// # Add a typedef before the functions that use it.
+    pub fn add_typedef(&mut self, def: Typedef) {
+        self.items.push(Item::Typedef(def));
+    }
 
-                self.code.push('=');
-                self.code.push_str(&f.to_string());
-                self.code.push_str(";\n");
-            }
-            VarInit::Float(f) => {
-                self.code.push_str("float ");
-                self.code.push_str(name);
+    /// # Declare a file-scope variable, before all functions.
+    pub fn add_global(&mut self, global: Global) {
+        self.items.push(Item::Global(global));
+    }
 
-                self.code.push('=');
-                self.code.push_str(&f.to_string());
-                self.code.push_str(";\n");
-            }
-            VarInit::Int32(i) => {
-                self.code.push_str("int ");
-                self.code.push_str(name);
+    /// # Define a macro, before all functions.
+    pub fn add_macro(&mut self, def: Macro) {
+        self.items.push(Item::Macro(def));
+    }
 
-                self.code.push('=');
-                self.code.push_str(&i.to_string());
-                self.code.push_str(";\n");
-            }
-            VarInit::Int64(i) => {
-                self.code.push_str("int ");
-                self.code.push_str(name);
+    /// # Undefine a macro, before all functions added after it.
+    ///
+    /// Added after a function, it is emitted after that function, so the
+    /// function can still use the macro.
+    pub fn undef<S: Into<String>>(&mut self, name: S) {
+        self.items.push(Item::Undef(name.into()));
+    }
 
-                self.code.push('=');
-                self.code.push_str(&i.to_string());
-                self.code.push_str(";\n");
-            }
-            VarInit::SizeString(size) => {
-                self.code.push_str("char ");
-                self.code.push_str(name);
+    /// # Assert at compile time that `cond` holds, before all functions.
+    ///
+    /// See [`Block::static_assert`].
+    pub fn add_static_assert<S: Into<String>>(&mut self, cond: Expr, message: S) {
+        self.items.push(Item::StaticAssert(cond, message.into()));
+    }
 
-                self.code.push('[');
-                self.code.push_str(&size.to_string());
-                self.code.push_str("];\n");
-            }
+    /// # Render the C Code.
+    ///
+    /// Unlike `to_string`, which writes an `#error` line for invalid code,
+    /// this reports why the code is invalid.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{Code, Error};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.continue_();
+    ///
+    /// assert_eq!(code.render(), Err(Error::ContinueOutsideLoop));
+    /// assert_eq!(code.to_string(), "#error \"`continue` used outside of a loop\"\n");
+    /// ```
+    pub fn render(&self) -> Result<String, Error> {
+        self.render_with(&[])
+    }
+
+    /// Render the C Code, including `first` before all other includes.
+    pub(crate) fn render_with(&self, first: &[Include]) -> Result<String, Error> {
+        let derived = self.derived();
+
+        let mut includes: Vec<Include> = first
+            .iter()
+            .filter(|first| !self.requires.contains(first))
+            .cloned()
+            .collect();
+        includes.extend(self.requires.iter().cloned());
+
+        let mut printer = Printer::new(&self.style, self.standard);
+
+        printer.includes(&includes, &derived.headers, &self.include_comments)?;
+        printer.items(&self.items)?;
+
+        if self.has_main {
+            let exit = Stmt::Return(Some(Expr::Int32(self.exit)));
+            printer.function(&self.main, &[exit])?;
+        }
+
+        let code = printer.finish();
+        conditional::check_balanced(&code)?;
+
+        Ok(code)
+    }
+
+    /// What the items and the main function depend on.
+    pub(crate) fn derived(&self) -> Requires {
+        let mut derived = Requires::default();
+        for item in &self.items {
+            item.requires(&mut derived);
+        }
+        if self.has_main {
+            self.main.requires(&mut derived);
+        }
+
+        derived
+    }
+
+    /// # Add a conditional compilation region of file-scope items, built by `build`.
+    ///
+    /// Use the returned [`Conditional`] to add `#elif` and `#else` branches.
+    /// See [`Block::pp_if`] for a region of statements.
+    pub fn pp_if_items<F: FnOnce(&mut Vec<Item>)>(
+        &mut self,
+        cond: PpCondition,
+        build: F,
+    ) -> &mut Conditional<Vec<Item>> {
+        let mut items = vec![];
+        build(&mut items);
+
+        self.items
+            .push(Item::Conditional(Conditional::new(cond, items)));
+
+        match self.items.last_mut() {
+            Some(Item::Conditional(region)) => region,
+            _ => unreachable!(),
         }
     }
+
+    /// # Add a cleanup action to run whenever `main` returns, see [`Function::defer`].
+    pub fn defer<F: FnOnce(&mut Block)>(&mut self, build: F) {
+        self.main.defer(build);
+    }
+
+    /// # The `main` function.
+    pub fn main(&self) -> &Function {
+        &self.main
+    }
 }
 
-impl Display for Code<'_> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut require_string = String::new();
+impl Deref for Code {
+    type Target = Block;
+
+    fn deref(&self) -> &Block {
+        &self.main
+    }
+}
+
+impl DerefMut for Code {
+    fn deref_mut(&mut self) -> &mut Block {
+        &mut self.main
+    }
+}
+
+/// What C Code depends on: the system headers it needs, and the names it
+/// uses, by which a [`Project`] finds the headers of its own to include.
+///
+/// Struct, union and enum tags are named with their keyword: `struct vec2`.
+#[derive(Debug, Default)]
+pub(crate) struct Requires {
+    pub(crate) headers: Vec<&'static str>,
+    pub(crate) names: Vec<String>,
+}
 
-        for require in &self.requires {
-            require_string.push_str("#include<");
-            require_string.push_str(require);
-            require_string.push_str(">\n");
+impl Requires {
+    /// Add a header, unless it is already there.
+    pub(crate) fn header(&mut self, header: &'static str) {
+        if !self.headers.contains(&header) {
+            self.headers.push(header);
         }
+    }
 
-        writeln!(
-            f,
-            "{}int main() {{\n{}return {};\n}}",
-            require_string, self.code, self.exit
-        )
+    /// Add a name, unless it is already there.
+    pub(crate) fn name(&mut self, name: &str) {
+        if !self.names.iter().any(|known| known == name) {
+            self.names.push(name.to_string());
+        }
+    }
+}
+
+impl Display for Code {
+    /// Writes an `#error` line with the reason if the code cannot be
+    /// rendered, so it does not compile; see [`Code::render`] for the error.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.render() {
+            Ok(code) => write!(f, "{code}"),
+            Err(err) => writeln!(f, "#error {}", escape::string_literal(&err.to_string())),
+        }
     }
 }
 
@@ -482,6 +805,20 @@ fn test_include_valid() {
         assert!(code.to_string().contains("#include<stdio.h>"));
     }
 
+    #[test]
+    fn test_include_dedup_by_kind() {
+        let mut code = Code::new();
+
+        code.include("util.h");
+        code.include(String::from("util.h"));
+        code.include_local("util.h");
+
+        assert_eq!(
+            code.includes(),
+            &[Include::system("util.h"), Include::local("util.h")]
+        );
+    }
+
     #[test]
     fn test_func_no_args() {
         let mut code = Code::new();
@@ -526,9 +863,56 @@ fn test_variable_i64() {
 
         code.new_var("num", VarInit::Int64(i64::MAX));
 
+        assert!(code.to_string().starts_with("#include<stdint.h>\n"));
         assert!(code
             .to_string()
-            .contains(format!("int num={};", i64::MAX).as_str()));
+            .contains(format!("int64_t num={}LL;", i64::MAX).as_str()));
+    }
+
+    #[test]
+    fn test_variable_unsigned() {
+        let mut code = Code::new();
+
+        code.new_var("a", VarInit::UInt32(u32::MAX));
+        code.new_var("b", VarInit::UInt64(u64::MAX));
+        code.call_func_with_args("f", vec![CArg::UInt32(1), CArg::UInt64(2)]);
+
+        assert_eq!(
+            code.to_string(),
+            format!(
+                "#include<stdint.h>\nint main() {{\nunsigned int a={}U;\nuint64_t b={}ULL;\nf(1U,2ULL);\nreturn 0;\n}}\n",
+                u32::MAX,
+                u64::MAX
+            )
+        );
+    }
+
+    #[test]
+    fn test_minimum_values() {
+        let mut code = Code::new();
+
+        code.new_var("a", VarInit::Int32(i32::MIN));
+        code.new_var("b", VarInit::Int64(i64::MIN));
+        code.new_var(
+            "c",
+            VarInit::Expr(
+                VarTypes::Int32,
+                Expr::unary(UnOp::Neg, Expr::Int32(i32::MIN)),
+            ),
+        );
+        code.new_var(
+            "d",
+            VarInit::Expr(
+                VarTypes::Int64,
+                Expr::binary(BinOp::Mul, Expr::Int64(i64::MIN), Expr::Int64(-1)),
+            ),
+        );
+
+        assert_eq!(
+            code.to_string(),
+            "#include<stdint.h>\nint main() {\nint a=-2147483647 - 1;\nint64_t b=-9223372036854775807LL - 1;\n\
+             int c=-(-2147483647 - 1);\nint64_t d=(-9223372036854775807LL - 1) * -1LL;\nreturn 0;\n}\n"
+        );
     }
 
     #[test]
@@ -537,9 +921,7 @@ fn test_variable_float() {
 
         code.new_var("num", VarInit::Float(f32::MAX));
 
-        assert!(code
-            .to_string()
-            .contains(format!("float num={};", f32::MAX).as_str()));
+        assert!(code.to_string().contains("float num=3.4028235e38f;"));
     }
 
     #[test]
@@ -550,7 +932,7 @@ fn test_variable_double() {
 
         assert!(code
             .to_string()
-            .contains(format!("double num={};", f64::MAX).as_str()));
+            .contains("double num=1.7976931348623157e308;"));
     }
 
     #[test]
@@ -562,6 +944,17 @@ fn test_variable_bool() {
         assert!(code.to_string().contains("bool b=true;"));
     }
 
+    #[test]
+    fn test_bool_requires_stdbool_once() {
+        let mut code = Code::new();
+
+        code.include("stdbool.h");
+        code.new_var("a", VarInit::Bool(true));
+        code.call_func_with_args("f", vec![CArg::Bool(false)]);
+
+        assert_eq!(code.to_string().matches("#include<stdbool.h>").count(), 1);
+    }
+
     #[test]
     fn test_variable_char() {
         let mut code = Code::new();
@@ -580,6 +973,25 @@ fn test_variable_size_string() {
         assert!(code.to_string().contains("char msg[5];"));
     }
 
+    #[test]
+    fn test_variable_expr() {
+        let mut code = Code::new();
+
+        code.new_var(
+            "n",
+            VarInit::Expr(
+                VarTypes::Int32,
+                Expr::binary(
+                    BinOp::Mul,
+                    Expr::ident("a"),
+                    Expr::index(Expr::ident("b"), Expr::Int32(2)),
+                ),
+            ),
+        );
+
+        assert!(code.to_string().contains("int n=a * b[2];"));
+    }
+
     #[test]
     fn test_variable_ident() {
         let mut code = Code::new();
@@ -589,4 +1001,988 @@ fn test_variable_ident() {
 
         assert!(code.to_string().contains("char s[]=\"X\";\nchar t[]=s;"));
     }
+
+    #[test]
+    fn test_function_order() {
+        let mut code = Code::new();
+
+        code.add_func(Function::new("a"));
+        code.add_func(Function::new("b"));
+
+        assert_eq!(
+            code.to_string(),
+            "void a() {\n}\nvoid b() {\n}\nint main() {\nreturn 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_function_params() {
+        let mut func = Function::new("f");
+
+        func.returns(VarTypes::String);
+        func.param(VarTypes::String, "s");
+        func.param(VarTypes::Bool, "b");
+        func.ret(Expr::ident("s"));
+
+        let mut code = Code::new();
+
+        code.add_func(func);
+
+        let code = code.to_string();
+
+        assert!(code.starts_with("#include<stdbool.h>\n"));
+        assert!(code.contains("char *f(char s[],bool b) {\nreturn s;\n}\n"));
+    }
+
+    #[test]
+    fn test_if_nested() {
+        let mut code = Code::new();
+
+        code.if_(Expr::ident("a"), |b| {
+            b.if_(Expr::ident("b"), |b| b.call_func("f"));
+        });
+
+        assert!(code
+            .to_string()
+            .contains("if (a) {\nif (b) {\nf();\n}\n}\n"));
+    }
+
+    #[test]
+    fn test_if_requires() {
+        let mut code = Code::new();
+
+        code.if_(Expr::Bool(true), |_| {}).else_(|b| {
+            b.new_var("x", VarInit::Int32(1));
+        });
+
+        assert!(code.to_string().starts_with(
+            "#include<stdbool.h>\nint main() {\nif (true) {\n} else {\nint x=1;\n}\n"
+        ));
+    }
+
+    #[test]
+    fn test_loops() {
+        let mut code = Code::new();
+
+        code.do_while(|b| b.continue_(), Expr::ident("x"));
+        code.for_(None, None, None, |b| {
+            b.if_(Expr::ident("done"), |b| b.break_());
+        });
+
+        assert!(code
+            .to_string()
+            .contains("do {\ncontinue;\n} while (x);\nfor (;;) {\nif (done) {\nbreak;\n}\n}\n"));
+    }
+
+    #[test]
+    fn test_break_outside_loop_in_function() {
+        let mut func = Function::new("f");
+        func.if_(Expr::ident("x"), |b| b.break_());
+
+        let mut code = Code::new();
+        code.add_func(func);
+
+        assert_eq!(code.render(), Err(Error::BreakOutsideLoop));
+    }
+
+    #[test]
+    fn test_switch_attr_and_scoped_decl() {
+        let mut code = Code::new();
+
+        code.switch(Expr::ident("x"), |s| {
+            s.case(Expr::Int32(1), CaseEnd::FallthroughAttr, |b| {
+                b.new_var("y", VarInit::Int32(2));
+            });
+            s.default(CaseEnd::Break, |_| {});
+        });
+
+        assert!(code.to_string().contains(
+            "switch (x) {\ncase 1:\n{\nint y=2;\n}\n/* fallthrough */\ndefault:\nbreak;\n}\n"
+        ));
+
+        code.standard(Standard::C23);
+
+        assert!(code.to_string().contains(
+            "switch (x) {\ncase 1:\n{\nint y=2;\n}\n[[fallthrough]];\ndefault:\nbreak;\n}\n"
+        ));
+    }
+
+    #[test]
+    fn test_switch_errors() {
+        let mut code = Code::new();
+
+        code.switch(Expr::ident("x"), |s| {
+            s.default(CaseEnd::Fallthrough, |_| {});
+        });
+
+        assert_eq!(code.render(), Err(Error::FallthroughFromLastCase));
+
+        let mut code = Code::new();
+
+        code.switch(Expr::ident("x"), |s| {
+            s.default(CaseEnd::Break, |b| b.continue_());
+        });
+
+        assert_eq!(code.render(), Err(Error::ContinueOutsideLoop));
+    }
+
+    #[test]
+    fn test_struct_vars() {
+        let mut point = Struct::new("point");
+        point.field(VarTypes::Int32, "x");
+        point.field(VarTypes::Int32, "y");
+        point.string_field("label", 8);
+
+        let mut code = Code::new();
+
+        code.new_var("a", VarInit::Uninit(point.ty()));
+        code.new_var(
+            "b",
+            VarInit::Struct("point", vec![("x", Expr::Int32(1)), ("y", Expr::Int32(2))]),
+        );
+        code.new_var("c", VarInit::Ident(point.ty(), "b"));
+        code.add_struct(point);
+
+        assert_eq!(
+            code.to_string(),
+            "struct point {\nint x;\nint y;\nchar label[8];\n};\nint main() {\n\
+             struct point a;\nstruct point b={.x=1,.y=2};\nstruct point c=b;\nreturn 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_compound_literal() {
+        let literal = Expr::cast(
+            VarTypes::Struct("point".to_string()),
+            Expr::InitList(vec![(None, Expr::Int32(1)), (None, Expr::Int32(2))]),
+        );
+
+        assert_eq!(literal.to_string(), "(struct point){1,2}");
+    }
+
+    #[test]
+    fn test_enum_name_fn_skips_aliases() {
+        let mut level = Enum::new("level");
+        level.variant_with_value("LOW", 1);
+        level.variant("MID");
+        level.variant_with_value("DEFAULT", 2);
+        level.generate_name_fn();
+
+        let mut code = Code::new();
+
+        code.add_enum(level.clone());
+        code.new_var("l", VarInit::Expr(level.ty(), Expr::ident("MID")));
+
+        let code = code.to_string();
+
+        assert!(code.contains("enum level {\nLOW=1,\nMID,\nDEFAULT=2\n};\n"));
+        assert!(code.contains("case MID:\nreturn \"MID\";\n}\n"));
+        assert!(!code.contains("case DEFAULT:"));
+        assert!(code.contains("enum level l=MID;"));
+    }
+
+    #[test]
+    fn test_typedef_tagged_union() {
+        let mut value = Union::new("value");
+        value.field(VarTypes::Bool, "b");
+        value.string_field("s", 16);
+
+        let value = Typedef::new("value_t", value);
+        let name = Typedef::new("name_t", VarTypes::String);
+
+        let mut code = Code::new();
+
+        code.add_typedef(value);
+        code.add_typedef(name);
+
+        assert!(code.to_string().starts_with(
+            "#include<stdbool.h>\ntypedef union value {\nbool b;\nchar s[16];\n} value_t;\n\
+             typedef char name_t[];\n"
+        ));
+    }
+
+    #[test]
+    fn test_function_with_ctypes() {
+        let callback = CType::function(CType::Void, vec![CType::Void.pointer()]).pointer();
+
+        let mut func = Function::new("lookup");
+        func.returns(CType::Char.constant().pointer());
+        func.param(callback, "cb");
+        func.param(CType::SizeT, "n");
+        func.ret(Expr::cast(VarTypes::String, Expr::Int32(0)));
+
+        let mut code = Code::new();
+        code.add_func(func);
+
+        assert!(code.to_string().starts_with(
+            "#include<stddef.h>\nconst char *lookup(void (*cb)(void *),size_t n) {\nreturn (char *)0;\n}\n"
+        ));
+    }
+
+    #[test]
+    fn test_globals_before_functions() {
+        let mut table = Global::new(Decl::typed(
+            CType::Int.array(2).constant(),
+            "table",
+            Some(Expr::InitList(vec![
+                (None, Expr::Int32(1)),
+                (None, Expr::Int32(2)),
+            ])),
+        ));
+        table.storage(Storage::Static);
+
+        let mut func = Function::new("f");
+        func.ret_void();
+
+        let mut counter = Global::new(Decl::typed(CType::Int, "counter", None));
+        counter.thread_local();
+
+        let mut code = Code::new();
+
+        code.add_func(func);
+        code.add_global(table);
+        code.add_global(counter);
+
+        assert_eq!(
+            code.to_string(),
+            "static const int table[2]={1,2};\n_Thread_local int counter;\n\
+             void f() {\nreturn;\n}\nint main() {\nreturn 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_conditional_function_region() {
+        let mut func = Function::new("trace");
+        func.call_func("dump");
+
+        let mut code = Code::new();
+
+        code.pp_if_items(PpCondition::Defined("TRACE".to_string()), |items| {
+            items.push(Item::Include(Include::local("trace.h")));
+            items.push(Item::Function(func));
+        })
+        .pp_elif("LEVEL > 1", |items| {
+            items.push(Item::Global(Global::new(Decl::new(
+                "on",
+                VarInit::Bool(true),
+            ))));
+        });
+        code.add_global(Global::new(Decl::new("x", VarInit::Int32(1))));
+
+        assert_eq!(
+            code.to_string(),
+            "#include<stdbool.h>\n#ifdef TRACE\n#include \"trace.h\"\n#elif LEVEL > 1\nbool on=true;\n#endif\n\
+             int x=1;\n#ifdef TRACE\nvoid trace() {\ndump();\n}\n#elif LEVEL > 1\n#endif\n\
+             int main() {\nreturn 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_conditional_region_items_stay_in_place() {
+        let mut h = Function::new("h");
+        h.comment(Comment::line("body"));
+
+        let mut code = Code::library();
+
+        code.add_global(Global::new(Decl::new("a", VarInit::Int32(1))));
+        code.items_mut()
+            .push(Item::Comment(Comment::line("region")));
+        code.pp_if_items(PpCondition::Defined("X".to_string()), |items| {
+            items.push(Item::Global(Global::new(Decl::new("b", VarInit::Int32(2)))));
+            items.push(Item::Comment(Comment::line("helper")));
+            items.push(Item::Function(h));
+        });
+        code.add_global(Global::new(Decl::new(
+            "c",
+            VarInit::Expr(VarTypes::Int32, Expr::ident("b")),
+        )));
+
+        assert_eq!(
+            code.to_string(),
+            "int a=1;\n// region\n#ifdef X\nint b=2;\n#endif\nint c=b;\n\
+             #ifdef X\n// helper\nvoid h() {\n// body\n}\n#endif\n"
+        );
+    }
+
+    #[test]
+    fn test_conditional_unbalanced_output() {
+        let mut code = Code::new();
+
+        code.pp_if(PpCondition::If(String::new()), |b| b.call_func("f"));
+
+        assert!(matches!(
+            code.render(),
+            Err(Error::UnbalancedConditional(2, _))
+        ));
+
+        let mut code = Code::new();
+
+        code.call_func("f();\n#endif\nf");
+
+        assert!(code.render().is_err());
+    }
+
+    #[test]
+    fn test_new_var_typed_call() {
+        let mut code = Code::new();
+
+        code.include("string.h");
+        code.new_var(
+            "n",
+            VarInit::Typed(
+                CType::SizeT,
+                Expr::call_with_args("strlen", vec![CArg::Ident("s")]),
+            ),
+        );
+
+        assert_eq!(
+            code.to_string(),
+            "#include<string.h>\n#include<stddef.h>\nint main() {\nsize_t n=strlen(s);\nreturn 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_defer_in_main_and_void_function() {
+        let mut close = Function::new("close_all");
+        close.defer(|b| b.call_func("first"));
+        close.defer(|b| b.call_func("second"));
+        close.while_(Expr::Int32(1), |b| b.ret_void());
+        close.new_var("x", VarInit::Int32(1));
+
+        let mut code = Code::new();
+        code.add_func(close);
+        code.defer(|b| b.call_func("bye"));
+
+        assert_eq!(
+            code.to_string(),
+            "void close_all() {\nwhile (1) {\ngoto cleanup;\n}\nint x=1;\ncleanup:\nsecond();\nfirst();\n}\n\
+             int main() {\nint result;\nresult=0;\ncleanup:\nbye();\nreturn result;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_label_before_decl() {
+        let mut code = Code::new();
+
+        code.label("again");
+        code.new_var("x", VarInit::Int32(1));
+        code.if_(Expr::ident("x"), |b| b.goto("again"));
+
+        assert!(code.to_string().contains("again:;\nint x=1;\n"));
+    }
+
+    #[test]
+    fn test_label_errors() {
+        let mut code = Code::new();
+
+        code.goto("nowhere");
+
+        assert_eq!(
+            code.render(),
+            Err(Error::UndefinedLabel(
+                "main".to_string(),
+                "nowhere".to_string()
+            ))
+        );
+
+        let mut func = Function::new("f");
+        func.label("cleanup");
+        func.defer(|b| b.call_func("g"));
+
+        let mut code = Code::new();
+        code.add_func(func);
+
+        assert_eq!(
+            code.render(),
+            Err(Error::DuplicateLabel(
+                "f".to_string(),
+                "cleanup".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_comments_stay_with_items() {
+        let mut point = Struct::new("point");
+        point.field(VarTypes::Int32, "x");
+        point.set_comment(Comment::doc("A point."));
+
+        let mut code = Code::new();
+        code.items_mut()
+            .push(Item::Comment(Comment::line("helper")));
+        code.add_func(Function::new("helper"));
+        code.add_struct(point);
+        code.label("top");
+        code.comment(Comment::line("declared here"));
+        code.new_var("y", VarInit::Int32(0));
+
+        assert_eq!(
+            code.to_string(),
+            "/** A point. */\nstruct point {\nint x;\n};\n// helper\nvoid helper() {\n}\n\
+             int main() {\ntop:;\n// declared here\nint y=0;\nreturn 0;\n}\n"
+        );
+    }
+
+    fn styled_sample(style: Style) -> String {
+        let mut code = Code::new();
+        code.style(style);
+
+        code.if_(Expr::ident("a"), |b| b.call_func("f")).else_(|b| {
+            b.do_while(
+                |b| b.assign(Expr::ident("x"), Expr::Int32(1)),
+                Expr::ident("x"),
+            );
+        });
+        code.switch(Expr::ident("x"), |s| {
+            s.case(Expr::Int32(1), CaseEnd::Break, |b| {
+                b.new_var("y", VarInit::Int32(2))
+            });
+        });
+        code.label("end");
+
+        code.to_string()
+    }
+
+    #[test]
+    fn test_style_knr_tabs() {
+        let style = Style {
+            indent: Indent::Tabs,
+            ..Style::default()
+        };
+
+        assert_eq!(
+            styled_sample(style),
+            "int main() {\n\tif (a) {\n\t\tf();\n\t} else {\n\t\tdo {\n\t\t\tx=1;\n\
+             \t\t} while (x);\n\t}\n\tswitch (x) {\n\t\tcase 1:\n\t\t\t{\n\t\t\t\tint y=2;\n\
+             \t\t\t}\n\t\t\tbreak;\n\t}\nend:\n\treturn 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_style_gnu() {
+        let style = Style {
+            indent: Indent::Spaces(2),
+            braces: Braces::Gnu,
+            space_after_comma: true,
+            space_around_assign: true,
+            ..Style::default()
+        };
+
+        assert_eq!(
+            styled_sample(style),
+            "int main()\n{\n  if (a)\n    {\n      f();\n    }\n  else\n    {\n      do\n        {\n\
+             \x20         x = 1;\n        }\n      while (x);\n    }\n  switch (x)\n    {\n\
+             \x20     case 1:\n        {\n          int y = 2;\n        }\n        break;\n    }\n\
+             end:\n  return 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_style_allman_items() {
+        let mut point = Struct::new("point");
+        point.field(
+            CType::Function {
+                returns: Box::new(CType::Int),
+                params: vec![CType::Int, CType::Int],
+                variadic: false,
+            }
+            .pointer(),
+            "cmp",
+        );
+
+        let mut color = Enum::new("color");
+        color.variant_with_value("RED", 1);
+        color.variant("BLUE");
+
+        let mut code = Code::new();
+        code.style(Style {
+            indent: Indent::Spaces(4),
+            braces: Braces::Allman,
+            space_after_comma: true,
+            space_around_assign: true,
+            ..Style::default()
+        });
+        code.add_struct(point);
+        code.add_enum(color);
+        code.pp_if(PpCondition::Defined("X".to_string()), |b| b.call_func("x"));
+
+        assert_eq!(
+            code.to_string(),
+            "struct point\n{\n    int (*cmp)(int, int);\n};\nenum color\n{\n    RED = 1,\n    BLUE\n};\n\
+             int main()\n{\n#ifdef X\n    x();\n#endif\n    return 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_strings_escaped_everywhere() {
+        let mut code = Code::new();
+
+        code.new_var("path", VarInit::String("C:\\tmp\0"));
+        code.call_func_with_args("puts", vec![CArg::String("caf\u{e9} ok??!")]);
+
+        assert_eq!(
+            code.to_string(),
+            "int main() {\nchar path[]=\"C:\\\\tmp\\000\";\nputs(\"caf\\xc3\\xa9 ok?\\?!\");\nreturn 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_char_literals_escaped() {
+        let mut code = Code::new();
+
+        code.new_var("slash", VarInit::Char('\\'));
+        code.call_func_with_args("putchar", vec![CArg::Char('\''), CArg::Char('\n')]);
+
+        assert_eq!(
+            code.to_string(),
+            "int main() {\nchar slash='\\\\';\nputchar('\\'','\\n');\nreturn 0;\n}\n"
+        );
+
+        code.call_func_with_args("putchar", vec![CArg::Char('é')]);
+
+        assert_eq!(code.render(), Err(Error::NonAsciiChar('é')));
+        assert_eq!(Expr::Char('é').to_string(), "'?'");
+    }
+
+    #[test]
+    fn test_float_literals() {
+        let mut code = Code::new();
+
+        code.new_var("a", VarInit::Float(1.0));
+        code.new_var("b", VarInit::Double(f64::NEG_INFINITY));
+        code.call_func_with_args("f", vec![CArg::Float(f32::NAN), CArg::Double(-0.5)]);
+        code.new_var(
+            "c",
+            VarInit::Expr(VarTypes::Double, Expr::unary(UnOp::Neg, Expr::Double(-1.0))),
+        );
+
+        assert_eq!(
+            code.to_string(),
+            "#include<math.h>\nint main() {\nfloat a=1.0f;\ndouble b=-INFINITY;\n\
+             f(NAN,-0.5);\ndouble c=-(-1.0);\nreturn 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_hex_floats() {
+        let mut code = Code::new();
+
+        code.style(Style {
+            hex_floats: true,
+            ..Style::default()
+        });
+        code.new_var("a", VarInit::Float(0.1));
+        code.new_var("b", VarInit::Double(-3.0));
+
+        assert_eq!(
+            code.to_string(),
+            "int main() {\nfloat a=0x1.99999ap-4f;\ndouble b=-0x1.8p+1;\nreturn 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_standard_c89() {
+        let mut code = Code::new();
+
+        code.standard(Standard::C89);
+        code.new_var("i", VarInit::Int32(0));
+        code.comment(Comment::line("first\nsecond"));
+        code.call_func("f");
+
+        assert_eq!(
+            code.to_string(),
+            "int main() {\nint i=0;\n/*\n * first\n * second\n */\nf();\nreturn 0;\n}\n"
+        );
+
+        code.new_var("j", VarInit::Int32(1));
+
+        assert_eq!(
+            code.render(),
+            Err(Error::Unsupported(
+                "declarations after statements",
+                Standard::C89
+            ))
+        );
+
+        let mut code = Code::new();
+        code.standard(Standard::C89);
+        code.for_(
+            Some(ForInit::Decl(Decl::new("i", VarInit::Int32(0)))),
+            None,
+            None,
+            |_| {},
+        );
+
+        assert_eq!(
+            code.render(),
+            Err(Error::Unsupported(
+                "declarations in `for` loops",
+                Standard::C89
+            ))
+        );
+
+        let mut code = Code::new();
+        code.standard(Standard::C89);
+        code.new_var("n", VarInit::Int64(1));
+
+        assert_eq!(
+            code.render(),
+            Err(Error::Unsupported("`stdint.h`", Standard::C89))
+        );
+
+        let mut code = Code::new();
+        code.standard(Standard::C89);
+        code.style(Style {
+            hex_floats: true,
+            ..Style::default()
+        });
+        code.new_var("x", VarInit::Double(0.5));
+
+        assert_eq!(
+            code.render(),
+            Err(Error::Unsupported("hexadecimal floats", Standard::C89))
+        );
+    }
+
+    #[test]
+    fn test_standard_static_assert() {
+        let mut code = Code::library();
+
+        code.add_static_assert(Expr::ident("N"), "N must not be \"0\"");
+
+        assert_eq!(
+            code.to_string(),
+            "_Static_assert(N,\"N must not be \\\"0\\\"\");\n"
+        );
+
+        code.standard(Standard::C23);
+        assert_eq!(
+            code.to_string(),
+            "static_assert(N,\"N must not be \\\"0\\\"\");\n"
+        );
+
+        code.standard(Standard::C99);
+        assert_eq!(
+            code.render(),
+            Err(Error::Unsupported("static assertions", Standard::C99))
+        );
+    }
+
+    #[test]
+    fn test_standard_c23() {
+        let mut flag = Global::new(Decl::new("flag", VarInit::Bool(false)));
+        flag.thread_local();
+
+        let mut code = Code::new();
+        code.standard(Standard::C23);
+        code.add_global(flag);
+        code.label("top");
+        code.static_assert(Expr::Int32(1), "ok");
+
+        assert_eq!(
+            code.to_string(),
+            "thread_local bool flag=false;\nint main() {\ntop:;\nstatic_assert(1,\"ok\");\nreturn 0;\n}\n"
+        );
+
+        code.standard(Standard::C99);
+        assert_eq!(
+            code.render(),
+            Err(Error::Unsupported("thread-local variables", Standard::C99))
+        );
+    }
+
+    #[test]
+    fn test_header_standard() {
+        let mut header = Header::new("flags.h");
+        header.standard(Standard::C89);
+        header.add_global(Global::new(Decl::new("on", VarInit::Bool(true))));
+
+        assert_eq!(
+            header.render(),
+            Err(Error::Unsupported("`bool`", Standard::C89))
+        );
+
+        header.standard(Standard::C23);
+        assert_eq!(
+            header.render(),
+            Ok("#ifndef FLAGS_H\n#define FLAGS_H\nbool on=true;\n#endif\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_defer_result_errors() {
+        let mut compute = Function::new("compute");
+        compute.returns(VarTypes::Int32);
+        compute.defer(|b| b.call_func("done"));
+        compute.new_var("result", VarInit::Int32(3));
+        compute.ret(Expr::ident("result"));
+
+        let mut code = Code::library();
+        code.add_func(compute);
+
+        assert_eq!(
+            code.render(),
+            Err(Error::ReservedName(
+                "compute".to_string(),
+                "result".to_string()
+            ))
+        );
+
+        let mut check = Function::new("check");
+        check.returns(VarTypes::Int32);
+        check.defer(|b| b.call_func("done"));
+        check.if_(Expr::ident("x"), |b| b.ret_void());
+        check.ret(Expr::Int32(1));
+
+        let mut code = Code::library();
+        code.add_func(check);
+
+        assert_eq!(
+            code.render(),
+            Err(Error::ReturnWithoutValue("check".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_display_never_fails() {
+        let mut code = Code::new();
+        code.break_();
+
+        assert_eq!(
+            code.to_string(),
+            "#error \"`break` used outside of a loop or switch\"\n"
+        );
+
+        let mut code = Code::new();
+        code.new_var("c", VarInit::Char('é'));
+
+        assert_eq!(
+            code.to_string(),
+            "#error \"`\\xc3\\xa9` is not ASCII, it cannot be a character literal\"\n"
+        );
+    }
+
+    #[test]
+    fn test_standard_c89_types_and_regions() {
+        let unsupported = |feature| Err(Error::Unsupported(feature, Standard::C89));
+
+        let mut code = Code::new();
+        code.standard(Standard::C89);
+        code.declare(CType::LongLong, "n", None);
+
+        assert_eq!(code.render(), unsupported("`long long`"));
+
+        let mut copy = Function::new("copy");
+        copy.param(CType::Char.pointer().restrict(), "dst");
+
+        let mut code = Code::library();
+        code.standard(Standard::C89);
+        code.add_func(copy);
+
+        assert_eq!(code.render(), unsupported("`restrict`"));
+
+        let mut log = Macro::function("LOG", vec!["fmt"]);
+        log.variadic().param("__VA_ARGS__");
+
+        let mut code = Code::library();
+        code.standard(Standard::C89);
+        code.add_macro(log);
+
+        assert_eq!(code.render(), unsupported("variadic macros"));
+
+        let origin = Expr::Cast(
+            CType::Struct("point".to_string()),
+            Box::new(Expr::InitList(vec![(None, Expr::Int32(0))])),
+        );
+
+        let mut code = Code::new();
+        code.standard(Standard::C89);
+        code.push(Stmt::Expr(origin));
+
+        assert_eq!(code.render(), unsupported("compound literals"));
+
+        let mut code = Code::new();
+        code.standard(Standard::C89);
+        code.pp_if(PpCondition::Defined("X".to_string()), |b| {
+            b.new_var("early", VarInit::Int32(0));
+        });
+        code.new_var("also_early", VarInit::Int32(0));
+        code.call_func("g");
+
+        assert!(code.render().is_ok());
+
+        code.pp_if(PpCondition::Defined("Y".to_string()), |b| {
+            b.new_var("late", VarInit::Int32(1));
+        });
+
+        assert_eq!(code.render(), unsupported("declarations after statements"));
+    }
+
+    #[test]
+    fn test_invalid_type_definitions() {
+        let mut code = Code::library();
+        code.add_struct(Struct::anonymous());
+
+        assert_eq!(code.render(), Err(Error::AnonymousRecord("struct")));
+
+        let mut code = Code::library();
+        code.add_union(Union::anonymous());
+
+        assert_eq!(code.render(), Err(Error::AnonymousRecord("union")));
+
+        let mut code = Code::library();
+        code.add_enum(Enum::new("e"));
+
+        assert_eq!(code.render(), Err(Error::EmptyEnum("e".to_string())));
+    }
+
+    #[test]
+    fn test_defer_void_return_value_evaluated() {
+        let mut work = Function::new("work");
+        work.defer(|b| b.call_func("done"));
+        work.if_(Expr::ident("x"), |b| b.ret(Expr::call("do_work", vec![])));
+
+        let mut code = Code::library();
+        code.add_func(work);
+
+        assert_eq!(
+            code.to_string(),
+            "void work() {\nif (x) {\ndo_work();\ngoto cleanup;\n}\ncleanup:\ndone();\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_string_fields_and_unsized_locals() {
+        let mut person = Struct::new("person");
+        person.field(VarTypes::String, "name");
+        person.field(VarTypes::Int32, "age");
+
+        let mut value = Union::new("value");
+        value.field(VarTypes::String, "s");
+
+        let mut code = Code::new();
+        code.add_struct(person);
+        code.add_union(value);
+
+        assert_eq!(
+            code.to_string(),
+            "struct person {\nchar *name;\nint age;\n};\nunion value {\nchar *s;\n};\n\
+             int main() {\nreturn 0;\n}\n"
+        );
+
+        code.new_var("s", VarInit::Uninit(VarTypes::String));
+
+        assert_eq!(code.render(), Err(Error::UnsizedArray("s".to_string())));
+    }
+
+    #[test]
+    fn test_declarations_in_regions_after_labels() {
+        let mut code = Code::new();
+
+        code.label("again");
+        code.pp_if(PpCondition::Defined("X".to_string()), |b| {
+            b.new_var("y", VarInit::Int32(1));
+        });
+        code.switch(Expr::ident("x"), |s| {
+            s.case(Expr::Int32(1), CaseEnd::Break, |b| {
+                b.pp_if(PpCondition::Defined("X".to_string()), |b| {
+                    b.new_var("z", VarInit::Int32(2));
+                });
+            });
+        });
+        code.goto("again");
+
+        assert_eq!(
+            code.to_string(),
+            "int main() {\nagain:;\n#ifdef X\nint y=1;\n#endif\nswitch (x) {\ncase 1:\n{\n\
+             #ifdef X\nint z=2;\n#endif\n}\nbreak;\n}\ngoto again;\nreturn 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_undef_after_function() {
+        let mut f = Function::new("f");
+        f.returns(VarTypes::Int32);
+        f.ret(Expr::ident("N"));
+
+        let mut n = Macro::object("N");
+        n.text("4");
+
+        let mut m = Macro::object("N");
+        m.text("5");
+
+        let mut code = Code::library();
+        code.add_macro(n);
+        code.add_func(f);
+        code.undef("N");
+        code.add_macro(m);
+        code.add_global(Global::new(Decl::new("x", VarInit::Int32(1))));
+
+        assert_eq!(
+            code.to_string(),
+            "#define N 4\nint x=1;\nint f() {\nreturn N;\n}\n#undef N\n#define N 5\n"
+        );
+    }
+
+    #[test]
+    fn test_enum_names_and_values() {
+        let mut flags = Enum::new("");
+        flags.variant("A");
+
+        let mut code = Code::library();
+        code.add_enum(flags.clone());
+
+        assert_eq!(code.to_string(), "enum {\nA\n};\n");
+
+        flags.generate_name_fn();
+        let mut code = Code::library();
+        code.add_enum(flags);
+
+        assert_eq!(code.render(), Err(Error::AnonymousEnumNameFn));
+
+        let mut big = Enum::new("big");
+        big.variant_with_value("B", 1 << 40);
+
+        let mut code = Code::library();
+        code.add_enum(big);
+
+        assert_eq!(
+            code.render(),
+            Err(Error::Unsupported(
+                "enum values out of the range of `int`",
+                Standard::C17
+            ))
+        );
+
+        code.standard(Standard::C23);
+
+        assert_eq!(code.to_string(), "enum big {\nB=1099511627776\n};\n");
+
+        let mut last = Enum::new("last");
+        last.variant_with_value("MAX", i32::MAX.into());
+        last.variant("OVER");
+
+        let mut code = Code::library();
+        code.add_enum(last);
+
+        assert!(code.render().is_err());
+    }
+
+    #[test]
+    fn test_empty_records() {
+        let mut code = Code::library();
+        code.add_struct(Struct::new("e"));
+
+        assert_eq!(code.render(), Err(Error::EmptyRecord("e".to_string())));
+
+        let mut code = Code::library();
+        code.add_union(Union::new("u"));
+
+        assert_eq!(code.render(), Err(Error::EmptyRecord("u".to_string())));
+
+        let mut code = Code::library();
+        code.add_typedef(Typedef::new("t", Struct::anonymous()));
+
+        assert_eq!(code.render(), Err(Error::EmptyRecord("t".to_string())));
+    }
 }
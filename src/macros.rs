@@ -0,0 +1,266 @@
+//! # Preprocessor macros.
+
+use crate::{Error, Standard};
+
+/// # A `#define` macro.
+///
+/// The replacement list is built from pieces, so that parameters,
+/// stringification and token pasting can be checked when rendering.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{Code, Macro};
+///
+/// let mut max = Macro::object("MAX_USERS");
+/// max.text("64");
+///
+/// let mut square = Macro::function("SQUARE", vec!["x"]);
+/// square.text("((").param("x").text(")*(").param("x").text("))");
+///
+/// let mut field = Macro::function("FIELD", vec!["name"]);
+/// field.text("{").stringify("name").text(", &obj_").paste().param("name").text("}");
+///
+/// let mut code = Code::new();
+///
+/// code.add_macro(max);
+/// code.add_macro(square);
+/// code.add_macro(field);
+/// code.undef("MAX_USERS");
+///
+/// assert_eq!(code.to_string(), r#"
+/// #define MAX_USERS 64
+/// #define SQUARE(x) ((x)*(x))
+/// #define FIELD(name) {#name, &obj_##name}
+/// #undef MAX_USERS
+/// int main() {
+/// return 0;
+/// }
+/// "#.trim_start().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Macro {
+    name: String,
+    params: Option<Vec<String>>,
+    variadic: bool,
+    body: Vec<MacroPiece>,
+}
+
+/// # A piece of the replacement list of a macro.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroPiece {
+    /// Text copied as is.
+    Text(String),
+
+    /// A use of a parameter.
+    Param(String),
+
+    /// A parameter turned into a string literal: `#param`.
+    Stringify(String),
+
+    /// The token pasting operator: `##`.
+    Paste,
+}
+
+impl Macro {
+    /// # Create an object-like macro, with an empty replacement list.
+    pub fn object<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            params: None,
+            variadic: false,
+            body: vec![],
+        }
+    }
+
+    /// # Create a function-like macro, with an empty replacement list.
+    pub fn function<S: Into<String>, P: Into<String>>(name: S, params: Vec<P>) -> Self {
+        Self {
+            name: name.into(),
+            params: Some(params.into_iter().map(Into::into).collect()),
+            variadic: false,
+            body: vec![],
+        }
+    }
+
+    /// # Let the function-like macro take more arguments: `...`.
+    ///
+    /// They are used with `param("__VA_ARGS__")`.
+    pub fn variadic(&mut self) -> &mut Self {
+        self.variadic = true;
+        self
+    }
+
+    /// # Add text to the replacement list.
+    pub fn text<S: Into<String>>(&mut self, text: S) -> &mut Self {
+        self.body.push(MacroPiece::Text(text.into()));
+        self
+    }
+
+    /// # Add a use of a parameter to the replacement list.
+    pub fn param<S: Into<String>>(&mut self, param: S) -> &mut Self {
+        self.body.push(MacroPiece::Param(param.into()));
+        self
+    }
+
+    /// # Add a stringified parameter to the replacement list: `#param`.
+    pub fn stringify<S: Into<String>>(&mut self, param: S) -> &mut Self {
+        self.body.push(MacroPiece::Stringify(param.into()));
+        self
+    }
+
+    /// # Add the token pasting operator to the replacement list: `##`.
+    pub fn paste(&mut self) -> &mut Self {
+        self.body.push(MacroPiece::Paste);
+        self
+    }
+
+    /// # The name of the macro.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// # The parameters of the macro, `None` for object-like macros.
+    pub fn params(&self) -> Option<&[String]> {
+        self.params.as_deref()
+    }
+
+    /// # The replacement list of the macro.
+    pub fn body(&self) -> &[MacroPiece] {
+        &self.body
+    }
+
+    fn has_param(&self, name: &str) -> bool {
+        match &self.params {
+            Some(params) => {
+                params.iter().any(|param| param == name) || (self.variadic && name == "__VA_ARGS__")
+            }
+            None => false,
+        }
+    }
+
+    /// The `#define` line, checking the parameters and `##` placement,
+    /// and that variadic macros are available in `standard`.
+    pub(crate) fn render(&self, standard: Standard) -> Result<String, Error> {
+        let mut text = format!("#define {}", self.name);
+
+        if let Some(params) = &self.params {
+            let mut params = params.clone();
+            if self.variadic {
+                standard.require(Standard::C99, "variadic macros")?;
+                params.push("...".to_string());
+            }
+            text.push_str(&format!("({})", params.join(",")));
+        }
+
+        let first = self.body.first();
+        let last = self.body.last();
+        if first == Some(&MacroPiece::Paste) || last == Some(&MacroPiece::Paste) {
+            return Err(Error::PasteAtEdge(self.name.clone()));
+        }
+
+        let mut body = String::new();
+        for piece in &self.body {
+            match piece {
+                MacroPiece::Text(s) => body.push_str(&s.replace('\n', " \\\n")),
+                MacroPiece::Param(param) | MacroPiece::Stringify(param)
+                    if !self.has_param(param) =>
+                {
+                    return Err(Error::UnknownMacroParam(self.name.clone(), param.clone()));
+                }
+                MacroPiece::Param(param) => body.push_str(param),
+                MacroPiece::Stringify(param) => {
+                    body.push('#');
+                    body.push_str(param);
+                }
+                MacroPiece::Paste => body.push_str("##"),
+            }
+        }
+
+        if !body.is_empty() {
+            text.push(' ');
+            text.push_str(&body);
+        }
+
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variadic() {
+        let mut log = Macro::function("LOG", vec!["fmt"]);
+        log.variadic()
+            .text("printf(")
+            .param("fmt")
+            .text(", ")
+            .param("__VA_ARGS__")
+            .text(")");
+
+        assert_eq!(
+            log.render(Standard::default()),
+            Ok("#define LOG(fmt,...) printf(fmt, __VA_ARGS__)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_empty_and_multiline() {
+        assert_eq!(
+            Macro::object("GUARD").render(Standard::default()),
+            Ok("#define GUARD".to_string())
+        );
+
+        let mut swap = Macro::function("SWAP", vec!["a", "b"]);
+        swap.text("do {\nint t = ")
+            .param("a")
+            .text(";\n} while (0)");
+
+        assert_eq!(
+            swap.render(Standard::default()),
+            Ok("#define SWAP(a,b) do { \\\nint t = a; \\\n} while (0)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut m = Macro::object("M");
+        m.stringify("x");
+
+        assert_eq!(
+            m.render(Standard::default()),
+            Err(Error::UnknownMacroParam("M".to_string(), "x".to_string()))
+        );
+
+        let mut m = Macro::function("M", vec!["x"]);
+        m.param("x").paste();
+
+        assert_eq!(
+            m.render(Standard::default()),
+            Err(Error::PasteAtEdge("M".to_string()))
+        );
+
+        let mut m = Macro::function("M", vec!["x"]);
+        m.param("__VA_ARGS__");
+
+        assert!(m.render(Standard::default()).is_err());
+    }
+
+    #[test]
+    fn test_variadic_needs_c99() {
+        let mut log = Macro::function("LOG", vec!["fmt"]);
+        log.variadic()
+            .text("printf(")
+            .param("fmt")
+            .text(", ")
+            .param("__VA_ARGS__")
+            .text(")");
+
+        assert_eq!(
+            log.render(Standard::C89),
+            Err(Error::Unsupported("variadic macros", Standard::C89))
+        );
+    }
+}
@@ -0,0 +1,822 @@
+//! # The printer turning the statement tree into C source.
+
+use crate::{
+    AssignOp, Block, Braces, CType, CaseEnd, Comment, Conditional, Decl, Enum, Error, Expr, Field,
+    ForInit, Function, Global, Include, Item, Standard, Stmt, Struct, Style, Switch, Typedef,
+    TypedefTarget, Union,
+};
+
+/// Renders statements line by line into a `String`.
+pub(crate) struct Printer {
+    out: String,
+    style: Style,
+    standard: Standard,
+    /// Whether the output is a header, included by several sources.
+    header: bool,
+    depth: usize,
+    /// How many levels each open brace indents its body by.
+    steps: Vec<usize>,
+    loops: usize,
+    switches: usize,
+}
+
+impl Printer {
+    pub(crate) fn new(style: &Style, standard: Standard) -> Self {
+        Self {
+            out: String::new(),
+            style: style.clone(),
+            standard,
+            header: false,
+            depth: 0,
+            steps: vec![],
+            loops: 0,
+            switches: 0,
+        }
+    }
+
+    /// Write a header: functions defined in it are private to each source.
+    pub(crate) fn in_header(&mut self) {
+        self.header = true;
+    }
+
+    /// The storage class of functions defined here, so a header defines
+    /// them once in every source including it.
+    fn linkage(&self) -> &'static str {
+        match (self.header, self.standard) {
+            (false, _) => "",
+            (true, Standard::C89) => "static ",
+            (true, _) => "static inline ",
+        }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.out
+    }
+
+    /// Write one line of output, indented to the current depth.
+    pub(crate) fn line(&mut self, text: &str) {
+        self.out.push_str(&self.style.indentation(self.depth));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    /// Write a preprocessor directive, which always starts the line.
+    pub(crate) fn directive(&mut self, text: &str) {
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    /// Write `head` and the opening brace of the block after it.
+    ///
+    /// `top` blocks are the bodies of functions and types, which GNU style
+    /// does not indent the braces of.
+    fn open(&mut self, head: &str, top: bool) {
+        let step = match self.style.braces {
+            Braces::Gnu if !top => 2,
+            _ => 1,
+        };
+
+        if self.style.braces == Braces::KAndR {
+            self.line(&format!("{head} {{"));
+        } else {
+            self.line(head);
+            self.depth += step - 1;
+            self.line("{");
+            self.depth -= step - 1;
+        }
+
+        self.depth += step;
+        self.steps.push(step);
+    }
+
+    /// Write the closing brace of a block, followed by `tail` on the same line.
+    fn close(&mut self, tail: &str) {
+        let step = self.steps.pop().unwrap_or(1);
+
+        self.depth -= 1;
+        self.line(&format!("}}{tail}"));
+        self.depth -= step - 1;
+    }
+
+    /// Close a block and continue its statement with `next`, opening
+    /// another block after it if `reopen`: `} else {`, `} while (x);`.
+    fn close_then(&mut self, next: &str, reopen: bool) {
+        if self.style.braces != Braces::KAndR {
+            self.close("");
+            if reopen {
+                self.open(next, false);
+            } else {
+                self.line(next);
+            }
+            return;
+        }
+
+        self.steps.pop();
+        self.depth -= 1;
+        if reopen {
+            self.line(&format!("}} {next} {{"));
+            self.depth += 1;
+            self.steps.push(1);
+        } else {
+            self.line(&format!("}} {next}"));
+        }
+    }
+
+    /// An expression, in the style of the printer.
+    fn expr(&self, expr: &Expr) -> Result<String, Error> {
+        expr.check(self.standard, &self.style)?;
+
+        Ok(expr.render(&self.style))
+    }
+
+    /// Declare `name` with a type, in the style of the printer.
+    fn declare(&self, ty: &CType, name: &str) -> Result<String, Error> {
+        ty.check(self.standard)?;
+
+        Ok(ty.declare_styled(name, &self.style))
+    }
+
+    /// Write the given includes with their comments, then the derived
+    /// system headers not among them.
+    ///
+    /// Fails if a derived header is not available in the standard.
+    pub(crate) fn includes(
+        &mut self,
+        includes: &[Include],
+        derived: &[&'static str],
+        comments: &[(Include, Comment)],
+    ) -> Result<(), Error> {
+        for include in includes {
+            for (_, comment) in comments.iter().filter(|(of, _)| of == include) {
+                self.comment(comment);
+            }
+            self.directive(&include.directive());
+        }
+        for require in derived {
+            match *require {
+                // `bool`, `true` and `false` are keywords in C23.
+                "stdbool.h" if self.standard >= Standard::C23 => continue,
+                "stdbool.h" => self.standard.require(Standard::C99, "`bool`")?,
+                "stdint.h" => self.standard.require(Standard::C99, "`stdint.h`")?,
+                _ => {}
+            }
+
+            let include = Include::system(*require);
+            if !includes.contains(&include) {
+                self.directive(&include.directive());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write a comment, as a block comment before C99.
+    pub(crate) fn comment(&mut self, comment: &Comment) {
+        let lines = match comment {
+            Comment::Line(text) if self.standard < Standard::C99 => Comment::block(text).lines(),
+            _ => comment.lines(),
+        };
+
+        for line in lines {
+            self.line(&line);
+        }
+    }
+
+    /// Write file-scope items, functions after all other items.
+    ///
+    /// Comments stay with the item after them.
+    pub(crate) fn items(&mut self, items: &[Item]) -> Result<(), Error> {
+        for functions in [false, true] {
+            for item in part(items, functions) {
+                self.item(&item)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn item(&mut self, item: &Item) -> Result<(), Error> {
+        if let Some(comment) = item.attached_comment() {
+            self.comment(comment);
+        }
+
+        match item {
+            Item::Function(func) => self.function(func, &[])?,
+            Item::Prototype(func) => {
+                let signature = self.signature(func, true)?;
+                self.line(&format!("{signature};"));
+            }
+            Item::Struct(def) => self.structure(def)?,
+            Item::Enum(def) => self.enumeration(def)?,
+            Item::Union(def) => self.union(def)?,
+            Item::Typedef(def) => self.typedef(def)?,
+            Item::Global(global) => self.global(global)?,
+            Item::Macro(def) => self.directive(&def.render(self.standard)?),
+            Item::Undef(name) => self.directive(&format!("#undef {name}")),
+            Item::StaticAssert(cond, message) => self.static_assert(cond, message)?,
+            Item::Include(include) => self.directive(&include.directive()),
+            Item::Comment(comment) => self.comment(comment),
+            Item::Conditional(region) => {
+                self.conditional(region, |p, items: &Vec<Item>| p.items(items))?
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write a conditional compilation region, with `branch` writing each branch.
+    fn conditional<T, F>(&mut self, region: &Conditional<T>, branch: F) -> Result<(), Error>
+    where
+        F: Fn(&mut Self, &T) -> Result<(), Error>,
+    {
+        self.directive(&region.cond.directive());
+        branch(self, &region.then)?;
+        for (cond, then) in &region.elifs {
+            self.directive(&format!("#elif {cond}"));
+            branch(self, then)?;
+        }
+        if let Some(otherwise) = &region.otherwise {
+            self.directive("#else");
+            branch(self, otherwise)?;
+        }
+        self.directive("#endif");
+
+        Ok(())
+    }
+
+    /// Write a function definition, followed by `epilogue` at the end of its body.
+    pub(crate) fn function(&mut self, func: &Function, epilogue: &[Stmt]) -> Result<(), Error> {
+        let mut body = func.body().clone();
+        for stmt in epilogue {
+            body.push(stmt.clone());
+        }
+
+        let mut result = None;
+        if !func.deferred().is_empty() {
+            let returns = func.return_type().decay();
+            if returns != CType::Void {
+                check_result(func, &body)?;
+                result = Some((returns, DEFER_RESULT));
+            }
+
+            body.rewrite_returns(result.as_ref().map(|(_, name)| *name), DEFER_LABEL);
+            // Do not jump to the label right below.
+            if body.stmts().last() == Some(&Stmt::Goto(DEFER_LABEL.to_string())) {
+                body.stmts_mut().pop();
+            }
+
+            body.label(DEFER_LABEL);
+            for action in func.deferred().iter().rev() {
+                body.stmts_mut().extend(action.stmts().iter().cloned());
+            }
+            if let Some((_, name)) = result {
+                body.ret(Expr::ident(name));
+            }
+        }
+
+        check_labels(func.name(), &body)?;
+
+        let signature = self.signature(func, false)?;
+        self.open(&format!("{}{signature}", self.linkage()), true);
+        if let Some((returns, name)) = result {
+            let decl = self.declare(&returns, name)?;
+            self.line(&format!("{decl};"));
+        }
+        self.block(&body)?;
+        self.close("");
+
+        Ok(())
+    }
+
+    pub(crate) fn structure(&mut self, def: &Struct) -> Result<(), Error> {
+        if def.name().is_empty() {
+            return Err(Error::AnonymousRecord("struct"));
+        }
+
+        self.record(def.name(), &tagged("struct", def.name()), def.fields(), ";")
+    }
+
+    pub(crate) fn union(&mut self, def: &Union) -> Result<(), Error> {
+        if def.name().is_empty() {
+            return Err(Error::AnonymousRecord("union"));
+        }
+
+        self.record(def.name(), &tagged("union", def.name()), def.fields(), ";")
+    }
+
+    pub(crate) fn typedef(&mut self, def: &Typedef) -> Result<(), Error> {
+        match def.target() {
+            TypedefTarget::Type(ty) => {
+                let decl = self.declare(ty, def.name())?;
+                self.line(&format!("typedef {decl};"));
+                Ok(())
+            }
+            TypedefTarget::Struct(inner) => {
+                let head = format!("typedef {}", tagged("struct", inner.name()));
+                self.record(
+                    def.name(),
+                    &head,
+                    inner.fields(),
+                    &format!(" {};", def.name()),
+                )
+            }
+            TypedefTarget::Union(inner) => {
+                let head = format!("typedef {}", tagged("union", inner.name()));
+                self.record(
+                    def.name(),
+                    &head,
+                    inner.fields(),
+                    &format!(" {};", def.name()),
+                )
+            }
+        }
+    }
+
+    /// Write the fields of a struct or union, between `head {` and `}tail`.
+    fn record(
+        &mut self,
+        name: &str,
+        head: &str,
+        fields: &[Field],
+        tail: &str,
+    ) -> Result<(), Error> {
+        if fields.is_empty() {
+            return Err(Error::EmptyRecord(name.to_string()));
+        }
+
+        self.open(head, true);
+        for field in fields {
+            let decl = self.declare(&field.ty, &field.name)?;
+            self.line(&format!("{decl};"));
+        }
+        self.close(tail);
+
+        Ok(())
+    }
+
+    pub(crate) fn enumeration(&mut self, def: &Enum) -> Result<(), Error> {
+        if def.variants().is_empty() {
+            return Err(Error::EmptyEnum(def.name().to_string()));
+        }
+        if def.has_name_fn() && def.name().is_empty() {
+            return Err(Error::AnonymousEnumNameFn);
+        }
+        // Before C23 every value must fit an `int`.
+        let mut next = 0i128;
+        for variant in def.variants() {
+            let value = variant.value.map_or(next, i128::from);
+            if i32::try_from(value).is_err() {
+                self.standard
+                    .require(Standard::C23, "enum values out of the range of `int`")?;
+            }
+            next = value + 1;
+        }
+
+        self.open(&tagged("enum", def.name()), true);
+        for (i, variant) in def.variants().iter().enumerate() {
+            let mut text = variant.name.clone();
+            if let Some(value) = variant.value {
+                text.push_str(&format!("{}{value}", self.style.assign(AssignOp::Assign)));
+            }
+            // No trailing comma, C89 does not allow it.
+            if i + 1 < def.variants().len() {
+                text.push(',');
+            }
+            self.line(&text);
+        }
+        self.close(";");
+
+        if def.has_name_fn() {
+            let name = def.name();
+            self.open(
+                &format!("{}const char *{name}_name(enum {name} v)", self.linkage()),
+                true,
+            );
+            self.open("switch (v)", false);
+            for variant in def.distinct_variants() {
+                self.line(&format!("case {}:", variant.name));
+                self.depth += 1;
+                self.line(&format!("return {};", Expr::string(&variant.name)));
+                self.depth -= 1;
+            }
+            self.close("");
+            self.line("return \"\";");
+            self.close("");
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn global(&mut self, global: &Global) -> Result<(), Error> {
+        let mut text = String::new();
+
+        if let Some(storage) = global.storage_class() {
+            text.push_str(storage.keyword());
+            text.push(' ');
+        }
+        if global.is_thread_local() {
+            self.standard
+                .require(Standard::C11, "thread-local variables")?;
+            if self.standard >= Standard::C23 {
+                text.push_str("thread_local ");
+            } else {
+                text.push_str("_Thread_local ");
+            }
+        }
+        text.push_str(&self.decl_text(global.decl())?);
+
+        self.line(&format!("{text};"));
+
+        Ok(())
+    }
+
+    pub(crate) fn block(&mut self, block: &Block) -> Result<(), Error> {
+        let stmts = block.stmts();
+        let mut after_stmt = false;
+
+        for (i, stmt) in stmts.iter().enumerate() {
+            let (has_decl, has_stmt) = kinds(std::slice::from_ref(stmt));
+            if has_decl && after_stmt {
+                self.standard
+                    .require(Standard::C99, "declarations after statements")?;
+            }
+            after_stmt |= has_stmt;
+
+            if let Stmt::Label(name) = stmt {
+                // A label must be followed by a statement, and declarations
+                // are not statements, so label an empty one instead. What
+                // follows a conditional region depends on its condition.
+                let next = stmts[i + 1..]
+                    .iter()
+                    .find(|stmt| !matches!(stmt, Stmt::Comment(_)));
+                let text = match next {
+                    Some(Stmt::Conditional(_)) => format!("{name}:;"),
+                    Some(stmt) if !stmt.is_decl() => format!("{name}:"),
+                    _ => format!("{name}:;"),
+                };
+                self.label(&text);
+                continue;
+            }
+            self.stmt(stmt)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write a label, one level less indented than the statements around it.
+    fn label(&mut self, text: &str) {
+        let depth = self.depth;
+
+        self.depth = depth.saturating_sub(1);
+        self.line(text);
+        self.depth = depth;
+    }
+
+    /// Write the body of a loop, where `break` and `continue` are allowed.
+    fn loop_body(&mut self, body: &Block) -> Result<(), Error> {
+        self.loops += 1;
+        let result = self.block(body);
+        self.loops -= 1;
+
+        result
+    }
+
+    fn switch(&mut self, switch: &Switch) -> Result<(), Error> {
+        self.open(&format!("switch ({})", self.expr(&switch.value)?), false);
+        self.switches += 1;
+
+        for (i, case) in switch.cases.iter().enumerate() {
+            match &case.label {
+                Some(label) => self.line(&format!("case {}:", self.expr(label)?)),
+                None => self.line("default:"),
+            }
+            self.depth += 1;
+
+            // A declaration cannot directly follow a label, and would be in
+            // scope of the following cases, so give it its own braces.
+            let (scoped, _) = kinds(case.body.stmts());
+            if scoped {
+                self.line("{");
+                self.depth += 1;
+            }
+            self.block(&case.body)?;
+            if scoped {
+                self.depth -= 1;
+                self.line("}");
+            }
+
+            let last = i + 1 == switch.cases.len();
+            match case.end {
+                CaseEnd::Break => self.line("break;"),
+                CaseEnd::Fallthrough | CaseEnd::FallthroughAttr if last => {
+                    return Err(Error::FallthroughFromLastCase);
+                }
+                CaseEnd::FallthroughAttr if self.standard >= Standard::C23 => {
+                    self.line("[[fallthrough]];")
+                }
+                CaseEnd::Fallthrough | CaseEnd::FallthroughAttr if case.body.stmts().is_empty() => {
+                }
+                CaseEnd::Fallthrough | CaseEnd::FallthroughAttr => self.line("/* fallthrough */"),
+            }
+
+            self.depth -= 1;
+        }
+
+        self.switches -= 1;
+        self.close("");
+
+        Ok(())
+    }
+
+    pub(crate) fn stmt(&mut self, stmt: &Stmt) -> Result<(), Error> {
+        match stmt {
+            Stmt::Expr(expr) => self.line(&format!("{};", self.expr(expr)?)),
+            Stmt::Decl(decl) => self.line(&format!("{};", self.local_decl_text(decl)?)),
+            Stmt::Return(Some(value)) => self.line(&format!("return {};", self.expr(value)?)),
+            Stmt::Return(None) => self.line("return;"),
+            Stmt::If(stmt) => {
+                for (i, (cond, block)) in stmt.branches.iter().enumerate() {
+                    let cond = self.expr(cond)?;
+                    if i == 0 {
+                        self.open(&format!("if ({cond})"), false);
+                    } else {
+                        self.close_then(&format!("else if ({cond})"), true);
+                    }
+                    self.block(block)?;
+                }
+                if let Some(block) = &stmt.otherwise {
+                    self.close_then("else", true);
+                    self.block(block)?;
+                }
+                self.close("");
+            }
+            Stmt::While(cond, body) => {
+                self.open(&format!("while ({})", self.expr(cond)?), false);
+                self.loop_body(body)?;
+                self.close("");
+            }
+            Stmt::DoWhile(body, cond) => {
+                self.open("do", false);
+                self.loop_body(body)?;
+                self.close_then(&format!("while ({});", self.expr(cond)?), false);
+            }
+            Stmt::For(stmt) => {
+                let init = match &stmt.init {
+                    Some(ForInit::Decl(decl)) => {
+                        self.standard
+                            .require(Standard::C99, "declarations in `for` loops")?;
+                        self.local_decl_text(decl)?
+                    }
+                    Some(ForInit::Expr(expr)) => self.expr(expr)?,
+                    None => String::new(),
+                };
+                let cond = match &stmt.cond {
+                    Some(cond) => format!(" {}", self.expr(cond)?),
+                    None => String::new(),
+                };
+                let step = match &stmt.step {
+                    Some(step) => format!(" {}", self.expr(step)?),
+                    None => String::new(),
+                };
+
+                self.open(&format!("for ({init};{cond};{step})"), false);
+                self.loop_body(&stmt.body)?;
+                self.close("");
+            }
+            Stmt::Switch(switch) => self.switch(switch)?,
+            Stmt::Break if self.loops == 0 && self.switches == 0 => {
+                return Err(Error::BreakOutsideLoop)
+            }
+            Stmt::Break => self.line("break;"),
+            Stmt::Continue if self.loops == 0 => return Err(Error::ContinueOutsideLoop),
+            Stmt::Continue => self.line("continue;"),
+            Stmt::Label(name) => self.label(&format!("{name}:;")),
+            Stmt::Goto(name) => self.line(&format!("goto {name};")),
+            Stmt::Comment(comment) => self.comment(comment),
+            Stmt::Conditional(region) => self.conditional(region, Self::block)?,
+            Stmt::StaticAssert(cond, message) => self.static_assert(cond, message)?,
+        }
+
+        Ok(())
+    }
+
+    /// Write a static assertion, spelled as in the standard.
+    fn static_assert(&mut self, cond: &Expr, message: &str) -> Result<(), Error> {
+        self.standard.require(Standard::C11, "static assertions")?;
+        let keyword = if self.standard >= Standard::C23 {
+            "static_assert"
+        } else {
+            "_Static_assert"
+        };
+
+        let cond = self.expr(cond)?;
+        let message = crate::escape::string_literal(message);
+        self.line(&format!(
+            "{keyword}({cond}{}{message});",
+            self.style.comma()
+        ));
+
+        Ok(())
+    }
+
+    /// The return type, name and parameters of a function.
+    ///
+    /// A `prototype` without parameters says so with `(void)`: `()` declares
+    /// a function without a prototype before C23.
+    fn signature(&self, func: &Function, prototype: bool) -> Result<String, Error> {
+        let mut params = func
+            .params()
+            .iter()
+            .map(|param| self.declare(&param.ty, &param.name))
+            .collect::<Result<Vec<_>, _>>()?
+            .join(self.style.comma());
+        if prototype && params.is_empty() {
+            params.push_str("void");
+        }
+        // Functions cannot return arrays, `VarTypes::String` returns a pointer.
+        let returns = func.return_type().decay();
+
+        self.declare(&returns, &format!("{}({params})", func.name()))
+    }
+
+    /// The text of a declaration inside a function, where an array needs
+    /// a size or an initializer.
+    fn local_decl_text(&self, decl: &Decl) -> Result<String, Error> {
+        if let (CType::Array(_, None), None) = (&decl.ty, &decl.init) {
+            return Err(Error::UnsizedArray(decl.name.clone()));
+        }
+
+        self.decl_text(decl)
+    }
+
+    /// The text of a declaration, without the trailing `;`.
+    fn decl_text(&self, decl: &Decl) -> Result<String, Error> {
+        let mut text = self.declare(&decl.ty, &decl.name)?;
+
+        if let Some(init) = &decl.init {
+            text.push_str(&self.style.assign(AssignOp::Assign));
+            text.push_str(&self.expr(init)?);
+        }
+
+        Ok(text)
+    }
+}
+
+/// The label the cleanup actions of [`Function::defer`] start at.
+const DEFER_LABEL: &str = "cleanup";
+
+/// The variable holding the return value while [`Function::defer`] cleans up.
+const DEFER_RESULT: &str = "result";
+
+/// Whether `stmts` have declarations and other statements, looking into
+/// conditional regions but not other blocks; comments are neither.
+fn kinds(stmts: &[Stmt]) -> (bool, bool) {
+    let (mut decls, mut others) = (false, false);
+    for stmt in stmts {
+        match stmt {
+            Stmt::Comment(_) => {}
+            Stmt::Conditional(region) => {
+                for branch in region.branches() {
+                    let (d, o) = kinds(branch.stmts());
+                    decls |= d;
+                    others |= o;
+                }
+            }
+            stmt if stmt.is_decl() => decls = true,
+            _ => others = true,
+        }
+    }
+
+    (decls, others)
+}
+
+/// Check that every label of a function body is unique and every goto has one.
+fn check_labels(func: &str, body: &Block) -> Result<(), Error> {
+    let mut labels = vec![];
+    let mut gotos = vec![];
+    body.visit(&mut |stmt| match stmt {
+        Stmt::Label(name) => labels.push(name),
+        Stmt::Goto(name) => gotos.push(name),
+        _ => {}
+    });
+
+    for (i, label) in labels.iter().enumerate() {
+        if labels[..i].contains(label) {
+            return Err(Error::DuplicateLabel(func.to_string(), label.to_string()));
+        }
+    }
+    for goto in gotos {
+        if !labels.contains(&goto) {
+            return Err(Error::UndefinedLabel(func.to_string(), goto.to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that a function can hold its return value in [`DEFER_RESULT`]
+/// while it cleans up: it does not declare it, and always returns a value.
+fn check_result(func: &Function, body: &Block) -> Result<(), Error> {
+    let mut names: Vec<&str> = func.params().iter().map(|p| p.name.as_str()).collect();
+    let mut bare_return = false;
+    body.visit(&mut |stmt| match stmt {
+        Stmt::Decl(decl) => names.push(&decl.name),
+        Stmt::For(stmt) => {
+            if let Some(ForInit::Decl(decl)) = &stmt.init {
+                names.push(&decl.name);
+            }
+        }
+        Stmt::Return(None) => bare_return = true,
+        _ => {}
+    });
+
+    if names.contains(&DEFER_RESULT) {
+        return Err(Error::ReservedName(
+            func.name().to_string(),
+            DEFER_RESULT.to_string(),
+        ));
+    }
+    if bare_return {
+        return Err(Error::ReturnWithoutValue(func.name().to_string()));
+    }
+
+    Ok(())
+}
+
+/// The functions among `items` if `functions`, or all other items.
+///
+/// Comments stay with the item after them, trailing comments are not
+/// functions. A conditional region with both kinds of items is split in
+/// two regions with the same condition; the comments before it stay with
+/// the first.
+///
+/// An `#undef` after a function goes with the functions, so the function
+/// can still use the macro, and so do the macros and `#undef`s after it.
+fn part(items: &[Item], functions: bool) -> Vec<Item> {
+    let mut out = vec![];
+    let mut comments = vec![];
+    let mut after_function = false;
+    let mut late_macros = false;
+
+    for item in items {
+        let (kept, comments_kept) = match item {
+            Item::Comment(_) => {
+                comments.push(item.clone());
+                continue;
+            }
+            Item::Function(_) => {
+                after_function = true;
+                (functions.then(|| item.clone()), true)
+            }
+            Item::Undef(_) | Item::Macro(_) => {
+                late_macros |= after_function && matches!(item, Item::Undef(_));
+                ((functions == late_macros).then(|| item.clone()), true)
+            }
+            Item::Conditional(region) => {
+                let split = |functions| {
+                    let mut region = region.clone();
+                    for branch in region.branches_mut() {
+                        *branch = part(branch, functions);
+                    }
+                    region
+                };
+                let (others, funcs) = (split(false), split(true));
+                let has_others = others.branches().any(|branch| !branch.is_empty());
+                let has_funcs = funcs.branches().any(|branch| !branch.is_empty());
+                after_function |= has_funcs;
+
+                match (functions, has_others, has_funcs) {
+                    (false, true, _) | (false, false, false) => {
+                        (Some(Item::Conditional(others)), true)
+                    }
+                    (true, _, true) => (Some(Item::Conditional(funcs)), !has_others),
+                    _ => (None, false),
+                }
+            }
+            _ => ((!functions).then(|| item.clone()), true),
+        };
+
+        if let Some(kept) = kept {
+            if comments_kept {
+                out.append(&mut comments);
+            }
+            out.push(kept);
+        }
+        comments.clear();
+    }
+
+    if !functions {
+        out.append(&mut comments);
+    }
+
+    out
+}
+
+/// The `struct` or `union` keyword, followed by the tag if there is one.
+fn tagged(keyword: &str, name: &str) -> String {
+    if name.is_empty() {
+        keyword.to_string()
+    } else {
+        format!("{keyword} {name}")
+    }
+}
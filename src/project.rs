@@ -0,0 +1,743 @@
+//! # Projects of several source files and headers.
+
+use std::fs;
+use std::io;
+use std::path::{Component, Path};
+
+use crate::printer::Printer;
+use crate::{
+    Code, Comment, Enum, Error, Expr, Function, Global, Include, Item, Macro, Requires, Standard,
+    Struct, Style, Typedef, Union,
+};
+
+/// # How a header protects itself from being included twice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Guard {
+    /// `#ifndef NAME`, `#define NAME` and `#endif` around the header.
+    Define(String),
+
+    /// `#pragma once` at the top of the header.
+    PragmaOnce,
+}
+
+/// # A header file.
+///
+/// Functions are declared in a header by their signature, see
+/// [`Header::declare_func`]. Functions defined in a header with
+/// [`Header::add_func`] are `static inline` (`static` in C89), so every
+/// source including the header gets its own copy instead of a duplicate
+/// definition.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{Function, Header, Struct, VarTypes};
+///
+/// let mut vec2 = Struct::new("vec2");
+/// vec2.field(VarTypes::Float, "x");
+/// vec2.field(VarTypes::Float, "y");
+///
+/// let mut len = Function::new("vec2_len");
+/// len.returns(VarTypes::Float);
+/// len.param(vec2.ty(), "v");
+///
+/// let mut header = Header::new("math/vec2.h");
+///
+/// header.add_struct(vec2);
+/// header.declare_func(&len);
+///
+/// assert_eq!(header.render().unwrap(), r#"
+/// #ifndef MATH_VEC2_H
+/// #define MATH_VEC2_H
+/// struct vec2 {
+/// float x;
+/// float y;
+/// };
+/// float vec2_len(struct vec2 v);
+/// #endif
+/// "#.trim_start().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Header {
+    name: String,
+    guard: Guard,
+    includes: Vec<Include>,
+    include_comments: Vec<(Include, Comment)>,
+    items: Vec<Item>,
+    style: Style,
+    standard: Standard,
+}
+
+impl Header {
+    /// # Create a new empty header, guarded by a macro named after the file.
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        let name = name.into();
+        let guard = Guard::Define(guard_macro(
+            &file_name(&name).unwrap_or_else(|_| name.clone()),
+        ));
+
+        Self {
+            name,
+            guard,
+            includes: vec![],
+            include_comments: vec![],
+            items: vec![],
+            style: Style::default(),
+            standard: Standard::default(),
+        }
+    }
+
+    /// # Set how the header is guarded.
+    pub fn guard(&mut self, guard: Guard) {
+        self.guard = guard;
+    }
+
+    /// # Guard the header with `#pragma once`.
+    pub fn pragma_once(&mut self) {
+        self.guard = Guard::PragmaOnce;
+    }
+
+    /// # Set how the header is laid out, see [`Style`].
+    pub fn style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    /// # Set the C standard the header is written for, see [`Standard`].
+    pub fn standard(&mut self, standard: Standard) {
+        self.standard = standard;
+    }
+
+    /// # The file name of the header, relative to the output directory.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// # How the header is guarded.
+    pub fn guard_kind(&self) -> &Guard {
+        &self.guard
+    }
+
+    /// # #include < a system header into the header. >
+    pub fn include<S: Into<String>>(&mut self, file: S) {
+        self.add_include(Include::System(file.into()));
+    }
+
+    /// # #include " a local header into the header. "
+    pub fn include_local<S: Into<String>>(&mut self, file: S) {
+        self.add_include(Include::Local(file.into()));
+    }
+
+    /// # Add an include, unless the same file is already included the same way.
+    pub fn add_include(&mut self, include: Include) {
+        if self.includes.contains(&include) {
+            return;
+        }
+        self.includes.push(include);
+    }
+
+    /// # Add an include with a comment written right before it.
+    pub fn comment_include(&mut self, include: Include, comment: Comment) {
+        self.include_comments.push((include.clone(), comment));
+        self.add_include(include);
+    }
+
+    /// # The includes, in the order they are emitted.
+    pub fn includes(&self) -> &[Include] {
+        &self.includes
+    }
+
+    /// # Declare a function by its signature, its body is not emitted.
+    pub fn declare_func(&mut self, func: &Function) {
+        self.items.push(Item::Prototype(func.clone()));
+    }
+
+    /// # Define a function in the header, `static inline` (`static` in C89).
+    pub fn add_func(&mut self, func: Function) {
+        self.items.push(Item::Function(func));
+    }
+
+    /// # Define a struct in the header.
+    pub fn add_struct(&mut self, def: Struct) {
+        self.items.push(Item::Struct(def));
+    }
+
+    /// # Define an enum in the header.
+    pub fn add_enum(&mut self, def: Enum) {
+        self.items.push(Item::Enum(def));
+    }
+
+    /// # Define a union in the header.
+    pub fn add_union(&mut self, def: Union) {
+        self.items.push(Item::Union(def));
+    }
+
+    /// # Add a typedef to the header.
+    pub fn add_typedef(&mut self, def: Typedef) {
+        self.items.push(Item::Typedef(def));
+    }
+
+    /// # Declare a file-scope variable in the header, usually `extern`.
+    pub fn add_global(&mut self, global: Global) {
+        self.items.push(Item::Global(global));
+    }
+
+    /// # Define a macro in the header.
+    pub fn add_macro(&mut self, def: Macro) {
+        self.items.push(Item::Macro(def));
+    }
+
+    /// # Assert at compile time that `cond` holds, in the header.
+    pub fn add_static_assert<S: Into<String>>(&mut self, cond: Expr, message: S) {
+        self.items.push(Item::StaticAssert(cond, message.into()));
+    }
+
+    /// # The items of the header, in the order they are emitted.
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    /// # The items of the header, for editing.
+    pub fn items_mut(&mut self) -> &mut Vec<Item> {
+        &mut self.items
+    }
+
+    /// # Render the header.
+    pub fn render(&self) -> Result<String, Error> {
+        self.render_with(&[])
+    }
+
+    /// Render the header, including `first` before all other includes.
+    fn render_with(&self, first: &[Include]) -> Result<String, Error> {
+        let derived = self.derived();
+
+        let mut includes: Vec<Include> = first
+            .iter()
+            .filter(|first| !self.includes.contains(first))
+            .cloned()
+            .collect();
+        includes.extend(self.includes.iter().cloned());
+
+        let mut printer = Printer::new(&self.style, self.standard);
+        printer.in_header();
+
+        match &self.guard {
+            Guard::Define(name) => {
+                printer.directive(&format!("#ifndef {name}"));
+                printer.directive(&format!("#define {name}"));
+            }
+            Guard::PragmaOnce => printer.directive("#pragma once"),
+        }
+
+        printer.includes(&includes, &derived.headers, &self.include_comments)?;
+        printer.items(&self.items)?;
+
+        if let Guard::Define(_) = self.guard {
+            printer.directive("#endif");
+        }
+
+        let code = printer.finish();
+        crate::conditional::check_balanced(&code)?;
+
+        Ok(code)
+    }
+
+    /// What the items of the header depend on.
+    fn derived(&self) -> Requires {
+        let mut derived = Requires::default();
+        for item in &self.items {
+            item.requires(&mut derived);
+        }
+
+        derived
+    }
+}
+
+/// # A project of several source files and headers.
+///
+/// A source file includes the header next to it with the same name first,
+/// so `vec2.c` starts with `#include "vec2.h"` when the project has a
+/// `vec2.h`. After it, every file includes the headers of the project that
+/// define the types, functions, variables, enum constants and macros it
+/// uses. A name defined in several headers is taken from the first one.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{Code, Expr, Function, Header, Project, VarTypes};
+///
+/// let mut answer = Function::new("answer");
+/// answer.returns(VarTypes::Int32);
+/// answer.ret(Expr::Int32(42));
+///
+/// let mut header = Header::new("answer.h");
+/// header.declare_func(&answer);
+///
+/// let mut library = Code::library();
+/// library.add_func(answer);
+///
+/// let mut program = Code::new();
+/// program.call_func("answer");
+///
+/// let mut project = Project::new();
+///
+/// project.add_header(header);
+/// project.add_source("answer.c", library);
+/// project.add_source("main.c", program);
+///
+/// let files = project.render().unwrap();
+///
+/// assert_eq!(files[1], ("answer.c".to_string(), r#"
+/// #include "answer.h"
+/// int answer() {
+/// return 42;
+/// }
+/// "#.trim_start().to_string()));
+/// assert_eq!(files[2], ("main.c".to_string(), r#"
+/// #include "answer.h"
+/// int main() {
+/// answer();
+/// return 0;
+/// }
+/// "#.trim_start().to_string()));
+/// ```
+#[derive(Default)]
+pub struct Project {
+    headers: Vec<Header>,
+    sources: Vec<(String, Code)>,
+}
+
+impl Project {
+    /// # Create a new empty project.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Add a header to the project.
+    pub fn add_header(&mut self, header: Header) {
+        self.headers.push(header);
+    }
+
+    /// # Add a source file to the project, by its file name.
+    pub fn add_source<S: Into<String>>(&mut self, name: S, code: Code) {
+        self.sources.push((name.into(), code));
+    }
+
+    /// # The headers of the project.
+    pub fn headers(&self) -> &[Header] {
+        &self.headers
+    }
+
+    /// # The source files of the project, with their file names.
+    pub fn sources(&self) -> &[(String, Code)] {
+        &self.sources
+    }
+
+    /// # Render every file of the project: the headers, then the source files.
+    ///
+    /// Each file is returned with its name, relative to the output directory
+    /// and without `.` components. Rendering fails with
+    /// [`Error::InvalidFileName`] for an absolute name or one with `..`.
+    pub fn render(&self) -> Result<Vec<(String, String)>, Error> {
+        let mut files: Vec<(String, String)> = vec![];
+
+        let headers = self
+            .headers
+            .iter()
+            .map(|header| file_name(&header.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let defined = self
+            .headers
+            .iter()
+            .map(|header| defines(&header.items))
+            .collect::<Vec<_>>();
+        for (name, header) in headers.iter().zip(&self.headers) {
+            let uses = header.derived();
+            let includes = wired_includes(name, &header.items, &uses, &headers, &defined);
+            files.push((name.clone(), header.render_with(&includes)?));
+        }
+        for (name, code) in &self.sources {
+            let name = file_name(name)?;
+            let mut includes: Vec<Include> = own_header(&headers, &name).into_iter().collect();
+            let uses = code.derived();
+            for include in wired_includes(&name, code.items(), &uses, &headers, &defined) {
+                if !includes.contains(&include) {
+                    includes.push(include);
+                }
+            }
+            files.push((name, code.render_with(&includes)?));
+        }
+
+        for (i, (name, _)) in files.iter().enumerate() {
+            if files[..i].iter().any(|(other, _)| other == name) {
+                return Err(Error::DuplicateFile(name.clone()));
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// # Write every file of the project into `dir`, creating directories as needed.
+    ///
+    /// Nothing is written if the project cannot be rendered.
+    pub fn write<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        let files = self
+            .render()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        for (name, contents) in files {
+            let path = dir.as_ref().join(name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, contents)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The includes of the headers defining the names a file uses, in the order
+/// of the headers. Names the file defines itself are skipped, the others
+/// come from the first header defining them.
+fn wired_includes(
+    file: &str,
+    items: &[Item],
+    uses: &Requires,
+    headers: &[String],
+    defined: &[Vec<String>],
+) -> Vec<Include> {
+    let own = defines(items);
+
+    let mut wanted = vec![false; headers.len()];
+    for name in uses.names.iter().filter(|name| !own.contains(name)) {
+        if let Some(i) = defined.iter().position(|names| names.contains(name)) {
+            wanted[i] = true;
+        }
+    }
+
+    headers
+        .iter()
+        .zip(wanted)
+        .filter(|(header, wanted)| *wanted && header.as_str() != file)
+        .map(|(header, _)| Include::local(relative_path(file, header)))
+        .collect()
+}
+
+/// The names the items define or declare.
+fn defines(items: &[Item]) -> Vec<String> {
+    let mut names = vec![];
+    for item in items {
+        item.defines(&mut names);
+    }
+
+    names
+}
+
+/// The path of project file `to`, relative to the directory of project file `from`.
+fn relative_path(from: &str, to: &str) -> String {
+    let from: Vec<&str> = from.split('/').collect();
+    let to: Vec<&str> = to.split('/').collect();
+
+    let dirs = &from[..from.len() - 1];
+    let common = dirs
+        .iter()
+        .zip(&to[..to.len() - 1])
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut parts = vec![".."; dirs.len() - common];
+    parts.extend(&to[common..]);
+    parts.join("/")
+}
+
+/// The include of the header next to a source file with the same name, if any.
+fn own_header(headers: &[String], source: &str) -> Option<Include> {
+    let path = Path::new(source).with_extension("h");
+    let header = headers.iter().find(|header| Path::new(header) == path)?;
+
+    let file = Path::new(header).file_name()?.to_string_lossy();
+    Some(Include::local(file))
+}
+
+/// A project file name without `.` components, if it stays inside the project.
+fn file_name(name: &str) -> Result<String, Error> {
+    let mut parts = vec![];
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(Error::InvalidFileName(name.to_string()))
+            }
+        }
+    }
+
+    if parts.is_empty() {
+        return Err(Error::InvalidFileName(name.to_string()));
+    }
+
+    Ok(parts.join("/"))
+}
+
+/// The guard macro for a header: `math/vec2.h` is guarded by `MATH_VEC2_H`.
+fn guard_macro(name: &str) -> String {
+    let mut guard: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    if !guard.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        guard.insert_str(0, "HEADER_");
+    }
+
+    guard
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinOp, CArg, CType, Decl, Expr, Storage, VarInit, VarTypes};
+
+    #[test]
+    fn test_guard_macro() {
+        assert_eq!(guard_macro("vec2.h"), "VEC2_H");
+        assert_eq!(guard_macro("my-lib/util.h"), "MY_LIB_UTIL_H");
+        assert_eq!(guard_macro("2d.h"), "HEADER_2D_H");
+        assert_eq!(guard_macro("_private.h"), "HEADER__PRIVATE_H");
+    }
+
+    #[test]
+    fn test_header_pragma_once_with_derived_includes() {
+        let mut flag = Function::new("flag");
+        flag.returns(CType::Bool);
+        flag.param(CType::SizeT, "n");
+
+        let mut header = Header::new("flag.h");
+        header.pragma_once();
+        header.include_local("types.h");
+        let mut count = Global::new(Decl::typed(VarTypes::Int32, "count", None));
+        count.storage(Storage::Extern);
+        header.add_global(count);
+        header.declare_func(&flag);
+
+        assert_eq!(
+            header.render().unwrap(),
+            "#pragma once\n#include \"types.h\"\n#include<stdbool.h>\n#include<stddef.h>\n\
+             extern int count;\nbool flag(size_t n);\n"
+        );
+    }
+
+    #[test]
+    fn test_prototype_without_params() {
+        let mut header = Header::new("init.h");
+        header.pragma_once();
+        header.declare_func(&Function::new("init"));
+
+        assert_eq!(header.render().unwrap(), "#pragma once\nvoid init(void);\n");
+    }
+
+    #[test]
+    fn test_own_header_in_subdirectory() {
+        let mut project = Project::new();
+
+        project.add_header(Header::new("src/util.h"));
+        project.add_source("src/util.c", Code::library());
+        project.add_source("main.c", Code::library());
+
+        let files = project.render().unwrap();
+
+        assert_eq!(files[1].1, "#include \"util.h\"\n");
+        assert_eq!(files[2].1, "");
+    }
+
+    #[test]
+    fn test_own_header_not_included_twice() {
+        let mut code = Code::library();
+        code.include("stdio.h");
+        code.include_local("util.h");
+
+        let mut project = Project::new();
+        project.add_header(Header::new("util.h"));
+        project.add_source("util.c", code);
+
+        let files = project.render().unwrap();
+
+        assert_eq!(files[1].1, "#include<stdio.h>\n#include \"util.h\"\n");
+    }
+
+    #[test]
+    fn test_wired_includes() {
+        let mut vec2 = Struct::new("vec2");
+        vec2.field(VarTypes::Float, "x");
+
+        let mut len = Function::new("vec2_len");
+        len.returns(VarTypes::Float);
+        len.param(vec2.ty(), "v");
+
+        let mut types = Header::new("include/types.h");
+        types.pragma_once();
+        types.add_struct(vec2.clone());
+
+        let mut math = Header::new("include/math2.h");
+        math.pragma_once();
+        math.declare_func(&len);
+
+        let mut main = Code::new();
+        main.new_var("v", VarInit::Uninit(vec2.ty()));
+        main.call_func_with_args("vec2_len", vec![CArg::Ident("v")]);
+
+        let mut own = Code::library();
+        own.add_struct(vec2);
+
+        let mut project = Project::new();
+        project.add_header(types);
+        project.add_header(math);
+        project.add_source("src/main.c", main);
+        project.add_source("src/own.c", own);
+
+        let files = project.render().unwrap();
+
+        assert_eq!(
+            files[1].1,
+            "#pragma once\n#include \"types.h\"\nfloat vec2_len(struct vec2 v);\n"
+        );
+        assert!(files[2]
+            .1
+            .starts_with("#include \"../include/types.h\"\n#include \"../include/math2.h\"\n"));
+        assert_eq!(files[3].1, "struct vec2 {\nfloat x;\n};\n");
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(relative_path("a.c", "a.h"), "a.h");
+        assert_eq!(relative_path("src/a.c", "include/b.h"), "../include/b.h");
+        assert_eq!(relative_path("a.c", "include/lib/b.h"), "include/lib/b.h");
+        assert_eq!(relative_path("include/lib/a.h", "include/b.h"), "../b.h");
+    }
+
+    #[test]
+    fn test_duplicate_file() {
+        let mut project = Project::new();
+
+        project.add_source("main.c", Code::new());
+        project.add_source("main.c", Code::new());
+
+        assert_eq!(
+            project.render(),
+            Err(Error::DuplicateFile("main.c".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_enum_name_fn_in_header() {
+        let mut color = Enum::new("color");
+        color.variant("RED");
+        color.generate_name_fn();
+
+        let mut header = Header::new("color.h");
+        header.pragma_once();
+        header.add_enum(color);
+
+        assert_eq!(
+            header.render().unwrap(),
+            "#pragma once\nenum color {\nRED\n};\n\
+             static inline const char *color_name(enum color v) {\nswitch (v) {\n\
+             case RED:\nreturn \"RED\";\n}\nreturn \"\";\n}\n"
+        );
+
+        header.standard(Standard::C89);
+
+        assert!(header
+            .render()
+            .unwrap()
+            .contains("\nstatic const char *color_name(enum color v) {\n"));
+    }
+
+    #[test]
+    fn test_function_in_header() {
+        let mut twice = Function::new("twice");
+        twice.returns(VarTypes::Int32);
+        twice.param(VarTypes::Int32, "n");
+        twice.ret(Expr::binary(BinOp::Mul, Expr::ident("n"), Expr::Int32(2)));
+
+        let mut header = Header::new("twice.h");
+        header.pragma_once();
+        header.items_mut().push(Item::Function(twice.clone()));
+
+        assert_eq!(
+            header.render().unwrap(),
+            "#pragma once\nstatic inline int twice(int n) {\nreturn n * 2;\n}\n"
+        );
+
+        let mut header = Header::new("twice.h");
+        header.pragma_once();
+        header.standard(Standard::C89);
+        header.add_func(twice);
+
+        assert_eq!(
+            header.render().unwrap(),
+            "#pragma once\nstatic int twice(int n) {\nreturn n * 2;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_file_names() {
+        assert_eq!(file_name("./src//a.h"), Ok("src/a.h".to_string()));
+        assert_eq!(
+            file_name("/tmp/x.h"),
+            Err(Error::InvalidFileName("/tmp/x.h".to_string()))
+        );
+        assert_eq!(
+            file_name("src/../../x.h"),
+            Err(Error::InvalidFileName("src/../../x.h".to_string()))
+        );
+        assert_eq!(file_name("."), Err(Error::InvalidFileName(".".to_string())));
+
+        let mut project = Project::new();
+        project.add_header(Header::new("./a.h"));
+
+        assert_eq!(project.render().unwrap()[0].0, "a.h");
+
+        project.add_header(Header::new("a.h"));
+
+        assert_eq!(
+            project.render(),
+            Err(Error::DuplicateFile("a.h".to_string()))
+        );
+
+        let mut project = Project::new();
+        project.add_header(Header::new("../x.h"));
+
+        assert!(project.write(std::env::temp_dir()).is_err());
+    }
+
+    #[test]
+    fn test_write() {
+        let dir = std::env::temp_dir().join(format!("c-emit-project-{}", std::process::id()));
+
+        let mut main = Code::new();
+        main.ret(Expr::Int32(3));
+
+        let mut project = Project::new();
+        project.add_header(Header::new("include/app.h"));
+        project.add_source("src/main.c", main);
+        project.write(&dir).unwrap();
+
+        let header = fs::read_to_string(dir.join("include/app.h")).unwrap();
+        let source = fs::read_to_string(dir.join("src/main.c")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            header,
+            "#ifndef INCLUDE_APP_H\n#define INCLUDE_APP_H\n#endif\n"
+        );
+        assert_eq!(source, "int main() {\nreturn 3;\nreturn 0;\n}\n");
+    }
+}
@@ -0,0 +1,95 @@
+//! # The C standard the output is written for.
+
+use std::fmt::{Display, Formatter};
+
+use crate::Error;
+
+/// # The C standard the C Code is written for.
+///
+/// The output adapts where the standard has another spelling:
+///
+/// - `//` comments are written as `/* */` comments before C99.
+/// - `bool` comes from `stdbool.h` before C23, where it is a keyword.
+/// - Static assertions are `_Static_assert` before C23, `static_assert` after.
+/// - Thread-local variables are `_Thread_local` before C23, `thread_local` after.
+/// - `[[fallthrough]];` is a `/* fallthrough */` comment before C23.
+///
+/// Rendering fails with [`Error::Unsupported`] for what the standard does
+/// not have at all:
+///
+/// - C99: `bool`, `stdint.h` types, `long long` and its literals,
+///   `restrict`, `INFINITY` and `NAN`, hexadecimal floats, designated
+///   initializers, compound literals, variadic macros, declarations after
+///   statements and in `for` loops.
+/// - C11: static assertions and thread-local variables.
+/// - C23: enum values out of the range of `int`, variadic functions without
+///   parameters.
+///
+/// The default is C17.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{Code, Comment, Error, Standard, VarInit};
+///
+/// let mut code = Code::new();
+///
+/// code.standard(Standard::C89);
+/// code.comment(Comment::line("count down"));
+/// code.new_var("n", VarInit::Int32(3));
+///
+/// assert_eq!(code.to_string(), r#"
+/// int main() {
+/// /* count down */
+/// int n=3;
+/// return 0;
+/// }
+/// "#.trim_start().to_string());
+///
+/// code.new_var("done", VarInit::Bool(false));
+///
+/// assert_eq!(code.render(), Err(Error::Unsupported("`bool`", Standard::C89)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Standard {
+    /// ANSI C, also known as C90.
+    C89,
+
+    /// C99.
+    C99,
+
+    /// C11.
+    C11,
+
+    /// C17, which has the features of C11.
+    #[default]
+    C17,
+
+    /// C23.
+    C23,
+}
+
+impl Standard {
+    /// Fail with [`Error::Unsupported`] if `feature` needs a later standard than this.
+    pub(crate) fn require(self, since: Standard, feature: &'static str) -> Result<(), Error> {
+        if self < since {
+            Err(Error::Unsupported(feature, self))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Display for Standard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::C89 => "C89",
+            Self::C99 => "C99",
+            Self::C11 => "C11",
+            Self::C17 => "C17",
+            Self::C23 => "C23",
+        };
+
+        write!(f, "{name}")
+    }
+}
@@ -0,0 +1,982 @@
+//! # C Statements and Blocks.
+
+use crate::{
+    AssignOp, CType, Comment, Conditional, Expr, PpCondition, Requires, UnOp, VarInit, VarTypes,
+};
+
+/// # A C Statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    /// An expression evaluated for its side effects: `f(x);`.
+    Expr(Expr),
+
+    /// A variable declaration.
+    Decl(Decl),
+
+    /// A return statement, with an optional value.
+    Return(Option<Expr>),
+
+    /// An if / else if / else chain.
+    If(If),
+
+    /// A while loop: `while (cond) { ... }`.
+    While(Expr, Block),
+
+    /// A do-while loop: `do { ... } while (cond);`.
+    DoWhile(Block, Expr),
+
+    /// A for loop.
+    For(For),
+
+    /// A switch statement.
+    Switch(Switch),
+
+    /// A `break` statement, only valid inside a loop or switch.
+    Break,
+
+    /// A `continue` statement, only valid inside a loop.
+    Continue,
+
+    /// A label to jump to with [`Stmt::Goto`]: `name:`.
+    Label(String),
+
+    /// A jump to a label of the same function: `goto name;`.
+    Goto(String),
+
+    /// A comment.
+    Comment(Comment),
+
+    /// A conditional compilation region.
+    Conditional(Conditional<Block>),
+
+    /// A compile-time assertion of a condition, with a message.
+    StaticAssert(Expr, String),
+}
+
+impl Stmt {
+    /// Whether the statement is a declaration to C, which a label cannot
+    /// mark and C89 does not allow after other statements.
+    pub(crate) fn is_decl(&self) -> bool {
+        matches!(self, Self::Decl(_) | Self::StaticAssert(..))
+    }
+
+    /// The blocks nested directly in this statement.
+    fn blocks(&self) -> Vec<&Block> {
+        match self {
+            Self::If(stmt) => stmt
+                .branches
+                .iter()
+                .map(|(_, block)| block)
+                .chain(&stmt.otherwise)
+                .collect(),
+            Self::While(_, body) | Self::DoWhile(body, _) => vec![body],
+            Self::For(stmt) => vec![&stmt.body],
+            Self::Switch(stmt) => stmt.cases.iter().map(|case| &case.body).collect(),
+            Self::Conditional(region) => region.branches().collect(),
+            _ => vec![],
+        }
+    }
+
+    /// The blocks nested directly in this statement, for editing.
+    fn blocks_mut(&mut self) -> Vec<&mut Block> {
+        match self {
+            Self::If(stmt) => stmt
+                .branches
+                .iter_mut()
+                .map(|(_, block)| block)
+                .chain(&mut stmt.otherwise)
+                .collect(),
+            Self::While(_, body) | Self::DoWhile(body, _) => vec![body],
+            Self::For(stmt) => vec![&mut stmt.body],
+            Self::Switch(stmt) => stmt.cases.iter_mut().map(|case| &mut case.body).collect(),
+            Self::Conditional(region) => region.branches_mut().collect(),
+            _ => vec![],
+        }
+    }
+}
+
+/// # An if / else if / else chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct If {
+    /// The conditions and their blocks, the first is the `if`, the rest are `else if`s.
+    pub branches: Vec<(Expr, Block)>,
+
+    /// The `else` block.
+    pub otherwise: Option<Block>,
+}
+
+/// # A variable declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decl {
+    /// The type of the variable.
+    pub ty: CType,
+
+    /// The name of the variable.
+    pub name: String,
+
+    /// The initial value, `None` leaves the variable uninitialized.
+    pub init: Option<Expr>,
+}
+
+/// # A block of statements.
+///
+/// Everything [`Code`](crate::Code) emits into `main` is pushed into a
+/// `Block`, so the statements can be inspected and changed before printing.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{Code, Stmt};
+///
+/// let mut code = Code::new();
+///
+/// code.call_func("a");
+/// code.call_func("b");
+/// code.stmts_mut().reverse();
+///
+/// assert!(matches!(code.stmts()[0], Stmt::Expr(_)));
+/// assert_eq!(code.to_string(), r#"
+/// int main() {
+/// b();
+/// a();
+/// return 0;
+/// }
+/// "#.trim_start().to_string());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Block {
+    stmts: Vec<Stmt>,
+}
+
+impl Decl {
+    /// # Make a declaration from a variable initialization.
+    pub fn new<S: Into<String>>(name: S, value: VarInit) -> Self {
+        let (ty, init) = match value {
+            VarInit::String(s) => (VarTypes::String.into(), Some(Expr::string(s))),
+            VarInit::Ident(ty, ident) => (ty.into(), Some(Expr::ident(ident))),
+            VarInit::Int32(n) => (VarTypes::Int32.into(), Some(Expr::Int32(n))),
+            VarInit::Int64(n) => (VarTypes::Int64.into(), Some(Expr::Int64(n))),
+            VarInit::UInt32(n) => (VarTypes::UInt32.into(), Some(Expr::UInt32(n))),
+            VarInit::UInt64(n) => (VarTypes::UInt64.into(), Some(Expr::UInt64(n))),
+            VarInit::Float(n) => (CType::Float, Some(Expr::Float(n))),
+            VarInit::Double(n) => (CType::Double, Some(Expr::Double(n))),
+            VarInit::Bool(b) => (CType::Bool, Some(Expr::Bool(b))),
+            VarInit::Char(c) => (CType::Char, Some(Expr::Char(c))),
+            VarInit::SizeString(size) => (CType::Char.array(size), None),
+            VarInit::Expr(ty, expr) => (ty.into(), Some(expr)),
+            VarInit::Typed(ty, expr) => (ty, Some(expr)),
+            VarInit::Struct(name, fields) => {
+                let fields = fields
+                    .into_iter()
+                    .map(|(field, value)| (Some(field.to_string()), value))
+                    .collect();
+
+                (
+                    CType::Struct(name.to_string()),
+                    Some(Expr::InitList(fields)),
+                )
+            }
+            VarInit::Uninit(ty) => (ty.into(), None),
+        };
+
+        Self {
+            ty,
+            name: name.into(),
+            init,
+        }
+    }
+
+    /// # Make a declaration of any type, with an optional initial value.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{CType, Decl, Expr};
+    ///
+    /// let decl = Decl::typed(CType::SizeT, "i", Some(Expr::Int32(0)));
+    ///
+    /// assert_eq!(decl.ty.to_string(), "size_t");
+    /// ```
+    pub fn typed<T: Into<CType>, S: Into<String>>(ty: T, name: S, init: Option<Expr>) -> Self {
+        Self {
+            ty: ty.into(),
+            name: name.into(),
+            init,
+        }
+    }
+
+    /// Collect what the declaration depends on.
+    pub(crate) fn requires(&self, out: &mut Requires) {
+        self.ty.requires(out);
+        if let Some(init) = &self.init {
+            init.requires(out);
+        }
+    }
+}
+
+/// # A for loop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct For {
+    /// The init clause.
+    pub init: Option<ForInit>,
+
+    /// The condition, `None` loops forever.
+    pub cond: Option<Expr>,
+
+    /// The step clause.
+    pub step: Option<Expr>,
+
+    /// The body of the loop.
+    pub body: Block,
+}
+
+/// # The init clause of a for loop.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ForInit {
+    /// Declare a loop variable: `for (int i=0; ...)`.
+    Decl(Decl),
+
+    /// Evaluate an expression.
+    Expr(Expr),
+}
+
+/// # A switch statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Switch {
+    /// The value being switched on.
+    pub value: Expr,
+
+    /// The cases, in order.
+    pub cases: Vec<Case>,
+}
+
+/// # A case of a switch statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    /// The case label, `None` is `default`.
+    pub label: Option<Expr>,
+
+    /// The statements of the case.
+    pub body: Block,
+
+    /// What happens at the end of the case.
+    pub end: CaseEnd,
+}
+
+/// # How a case of a switch statement ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseEnd {
+    /// Leave the switch with `break;`.
+    Break,
+
+    /// Fall through into the next case, marked with a `/* fallthrough */` comment.
+    Fallthrough,
+
+    /// Fall through into the next case with the C23 `[[fallthrough]];` attribute,
+    /// or as [`CaseEnd::Fallthrough`] before C23.
+    FallthroughAttr,
+}
+
+impl Switch {
+    /// # Make a switch statement without any cases.
+    pub fn new(value: Expr) -> Self {
+        Self {
+            value,
+            cases: vec![],
+        }
+    }
+
+    /// # Add a `case value:`, with the body built by `build`.
+    pub fn case<F: FnOnce(&mut Block)>(
+        &mut self,
+        value: Expr,
+        end: CaseEnd,
+        build: F,
+    ) -> &mut Self {
+        let mut body = Block::new();
+        build(&mut body);
+
+        self.cases.push(Case {
+            label: Some(value),
+            body,
+            end,
+        });
+        self
+    }
+
+    /// # Add the `default:` case, with the body built by `build`.
+    pub fn default<F: FnOnce(&mut Block)>(&mut self, end: CaseEnd, build: F) -> &mut Self {
+        let mut body = Block::new();
+        build(&mut body);
+
+        self.cases.push(Case {
+            label: None,
+            body,
+            end,
+        });
+        self
+    }
+}
+
+impl From<Decl> for ForInit {
+    fn from(decl: Decl) -> Self {
+        Self::Decl(decl)
+    }
+}
+
+impl From<Expr> for ForInit {
+    fn from(expr: Expr) -> Self {
+        Self::Expr(expr)
+    }
+}
+
+impl If {
+    /// # Make an if statement without any else branches.
+    pub fn new(cond: Expr, then: Block) -> Self {
+        Self {
+            branches: vec![(cond, then)],
+            otherwise: None,
+        }
+    }
+
+    /// # Add an `else if` branch, built by `build`.
+    pub fn else_if<F: FnOnce(&mut Block)>(&mut self, cond: Expr, build: F) -> &mut Self {
+        let mut block = Block::new();
+        build(&mut block);
+
+        self.branches.push((cond, block));
+        self
+    }
+
+    /// # Set the `else` branch, built by `build`.
+    pub fn else_<F: FnOnce(&mut Block)>(&mut self, build: F) {
+        let mut block = Block::new();
+        build(&mut block);
+
+        self.otherwise = Some(block);
+    }
+}
+
+impl Block {
+    /// # Create an empty block.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # The statements in the block.
+    pub fn stmts(&self) -> &[Stmt] {
+        &self.stmts
+    }
+
+    /// # The statements in the block, for editing.
+    pub fn stmts_mut(&mut self) -> &mut Vec<Stmt> {
+        &mut self.stmts
+    }
+
+    /// # Add a statement to the end of the block.
+    pub fn push(&mut self, stmt: Stmt) {
+        self.stmts.push(stmt);
+    }
+
+    /// # Call a function WITHOUT arguments.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::Code;
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.call_func("printf");
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// int main() {
+    /// printf();
+    /// return 0;
+    /// }
+    /// "#.trim_start().to_string());
+    /// ```
+    pub fn call_func(&mut self, func: &str) {
+        self.push(Stmt::Expr(Expr::call(func, vec![])));
+    }
+
+    /// # Call a function WITH arguments.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{Code, CArg};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.call_func_with_args("printf", vec![CArg::String("Hello, world!")]);
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// int main() {
+    /// printf("Hello, world!");
+    /// return 0;
+    /// }
+    /// "#.trim_start().to_string());
+    /// ```
+    ///
+    /// Arguments can also be any [`Expr`]:
+    ///
+    /// ```rust
+    /// use c_emit::{BinOp, Code, Expr};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.call_func_with_args("printf", vec![
+    ///     Expr::string("%d"),
+    ///     Expr::binary(BinOp::Add, Expr::call("strlen", vec![Expr::ident("s")]), Expr::Int32(1)),
+    /// ]);
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// int main() {
+    /// printf("%d",strlen(s) + 1);
+    /// return 0;
+    /// }
+    /// "#.trim_start().to_string());
+    /// ```
+    pub fn call_func_with_args<A: Into<Expr>>(&mut self, func: &str, args: Vec<A>) {
+        self.push(Stmt::Expr(Expr::call_with_args(func, args)));
+    }
+
+    /// # Make a new variable.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{Code, CArg, VarInit};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.new_var("a", VarInit::String("hello"));
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// int main() {
+    /// char a[]="hello";
+    /// return 0;
+    /// }
+    /// "#.trim_start().to_string());
+    ///
+    /// ```
+    ///
+    /// Any expression can initialize a variable of an explicit type:
+    ///
+    /// ```rust
+    /// use c_emit::{CArg, CType, Code, Expr, VarInit, VarTypes};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.new_var("n", VarInit::Expr(VarTypes::Int32, Expr::call_with_args("strlen", vec![CArg::Ident("s")])));
+    /// code.new_var("f", VarInit::Typed(
+    ///     CType::Named("FILE".to_string()).pointer(),
+    ///     Expr::call_with_args("fopen", vec![CArg::String("out.txt"), CArg::String("w")]),
+    /// ));
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// int main() {
+    /// int n=strlen(s);
+    /// FILE *f=fopen("out.txt","w");
+    /// return 0;
+    /// }
+    /// "#.trim_start().to_string());
+    /// ```
+    /// ## NOTE:
+    /// Use [`VarInit::SizeString`] to make a string variable uninitialized.
+    pub fn new_var<S: AsRef<str>>(&mut self, name: S, value: VarInit) {
+        self.push(Stmt::Decl(Decl::new(name.as_ref(), value)));
+    }
+
+    /// # Declare a variable of any type, with an optional initial value.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{CType, Code, Expr};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.declare(CType::Char.constant().pointer(), "msg", Some(Expr::string("hi")));
+    /// code.declare(CType::UInt8.array(16), "buf", None);
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// #include<stdint.h>
+    /// int main() {
+    /// const char *msg="hi";
+    /// uint8_t buf[16];
+    /// return 0;
+    /// }
+    /// "#.trim_start().to_string());
+    /// ```
+    pub fn declare<T: Into<CType>, S: Into<String>>(&mut self, ty: T, name: S, init: Option<Expr>) {
+        self.push(Stmt::Decl(Decl::typed(ty, name, init)));
+    }
+
+    /// # Assign a value to a variable, array element, member or dereferenced pointer.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{AssignOp, Code, Expr, VarInit};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.new_var("x", VarInit::Int32(1));
+    /// code.assign(Expr::ident("x"), Expr::Int32(5));
+    /// code.assign_op(AssignOp::Add, Expr::ident("x"), Expr::ident("y"));
+    /// code.assign(Expr::member(Expr::ident("p"), "x"), Expr::ident("x"));
+    /// code.increment(Expr::ident("x"));
+    /// code.decrement(Expr::deref(Expr::ident("n")));
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// int main() {
+    /// int x=1;
+    /// x=5;
+    /// x+=y;
+    /// p.x=x;
+    /// x++;
+    /// (*n)--;
+    /// return 0;
+    /// }
+    /// "#.trim_start().to_string());
+    /// ```
+    pub fn assign(&mut self, target: Expr, value: Expr) {
+        self.push(Stmt::Expr(Expr::assign(target, value)));
+    }
+
+    /// # Assign with a compound operator: `target+=value;`, ...
+    pub fn assign_op(&mut self, op: AssignOp, target: Expr, value: Expr) {
+        self.push(Stmt::Expr(Expr::assign_op(op, target, value)));
+    }
+
+    /// # Increment a value: `target++;`.
+    pub fn increment(&mut self, target: Expr) {
+        self.push(Stmt::Expr(Expr::unary(UnOp::PostInc, target)));
+    }
+
+    /// # Decrement a value: `target--;`.
+    pub fn decrement(&mut self, target: Expr) {
+        self.push(Stmt::Expr(Expr::unary(UnOp::PostDec, target)));
+    }
+
+    /// # Return a value from the function.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{Expr, Function, VarTypes};
+    ///
+    /// let mut answer = Function::new("answer");
+    ///
+    /// answer.returns(VarTypes::Int32);
+    /// answer.ret(Expr::Int32(42));
+    ///
+    /// assert_eq!(answer.stmts().len(), 1);
+    /// ```
+    pub fn ret<E: Into<Expr>>(&mut self, value: E) {
+        self.push(Stmt::Return(Some(value.into())));
+    }
+
+    /// # Return from a `void` function.
+    pub fn ret_void(&mut self) {
+        self.push(Stmt::Return(None));
+    }
+
+    /// # Add an if statement, with the block built by `build`.
+    ///
+    /// Use the returned [`If`] to add `else if` and `else` branches.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{BinOp, Code, CArg, Expr};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.if_(Expr::binary(BinOp::Lt, Expr::ident("x"), Expr::Int32(0)), |b| {
+    ///     b.call_func_with_args("puts", vec![CArg::String("negative")]);
+    /// })
+    /// .else_if(Expr::binary(BinOp::Eq, Expr::ident("x"), Expr::Int32(0)), |b| {
+    ///     b.call_func_with_args("puts", vec![CArg::String("zero")]);
+    /// })
+    /// .else_(|b| {
+    ///     b.call_func_with_args("puts", vec![CArg::String("positive")]);
+    /// });
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// int main() {
+    /// if (x < 0) {
+    /// puts("negative");
+    /// } else if (x == 0) {
+    /// puts("zero");
+    /// } else {
+    /// puts("positive");
+    /// }
+    /// return 0;
+    /// }
+    /// "#.trim_start().to_string());
+    /// ```
+    pub fn if_<F: FnOnce(&mut Block)>(&mut self, cond: Expr, build: F) -> &mut If {
+        let mut then = Block::new();
+        build(&mut then);
+
+        self.push(Stmt::If(If::new(cond, then)));
+
+        match self.stmts.last_mut() {
+            Some(Stmt::If(stmt)) => stmt,
+            _ => unreachable!(),
+        }
+    }
+
+    /// # Add a while loop, with the body built by `build`.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{Code, Expr};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.while_(Expr::call("running", vec![]), |b| {
+    ///     b.call_func("step");
+    /// });
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// int main() {
+    /// while (running()) {
+    /// step();
+    /// }
+    /// return 0;
+    /// }
+    /// "#.trim_start().to_string());
+    /// ```
+    pub fn while_<F: FnOnce(&mut Block)>(&mut self, cond: Expr, build: F) {
+        let mut body = Block::new();
+        build(&mut body);
+
+        self.push(Stmt::While(cond, body));
+    }
+
+    /// # Add a do-while loop, with the body built by `build`.
+    pub fn do_while<F: FnOnce(&mut Block)>(&mut self, build: F, cond: Expr) {
+        let mut body = Block::new();
+        build(&mut body);
+
+        self.push(Stmt::DoWhile(body, cond));
+    }
+
+    /// # Add a for loop, with the body built by `build`.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{BinOp, CArg, Code, Decl, Expr, UnOp, VarInit};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.for_(
+    ///     Some(Decl::new("i", VarInit::Int32(0)).into()),
+    ///     Some(Expr::binary(BinOp::Lt, Expr::ident("i"), Expr::Int32(10))),
+    ///     Some(Expr::unary(UnOp::PostInc, Expr::ident("i"))),
+    ///     |b| {
+    ///         b.call_func_with_args("printf", vec![CArg::String("%d"), CArg::Ident("i")]);
+    ///     },
+    /// );
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// int main() {
+    /// for (int i=0; i < 10; i++) {
+    /// printf("%d",i);
+    /// }
+    /// return 0;
+    /// }
+    /// "#.trim_start().to_string());
+    /// ```
+    pub fn for_<F: FnOnce(&mut Block)>(
+        &mut self,
+        init: Option<ForInit>,
+        cond: Option<Expr>,
+        step: Option<Expr>,
+        build: F,
+    ) {
+        let mut body = Block::new();
+        build(&mut body);
+
+        self.push(Stmt::For(For {
+            init,
+            cond,
+            step,
+            body,
+        }));
+    }
+
+    /// # Add a switch statement, with the cases added by `build`.
+    ///
+    /// Every case says explicitly whether it breaks or falls through, see
+    /// [`CaseEnd`]. A case that falls through with an empty body just stacks
+    /// its label onto the next one.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{CArg, CaseEnd, Code, Expr};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.switch(Expr::ident("kind"), |s| {
+    ///     s.case(Expr::ident("CIRCLE"), CaseEnd::Fallthrough, |_| {});
+    ///     s.case(Expr::ident("ELLIPSE"), CaseEnd::Break, |b| b.call_func("round"));
+    ///     s.case(Expr::ident("SQUARE"), CaseEnd::Fallthrough, |b| b.call_func("square"));
+    ///     s.default(CaseEnd::Break, |b| b.call_func("polygon"));
+    /// });
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// int main() {
+    /// switch (kind) {
+    /// case CIRCLE:
+    /// case ELLIPSE:
+    /// round();
+    /// break;
+    /// case SQUARE:
+    /// square();
+    /// /* fallthrough */
+    /// default:
+    /// polygon();
+    /// break;
+    /// }
+    /// return 0;
+    /// }
+    /// "#.trim_start().to_string());
+    /// ```
+    pub fn switch<F: FnOnce(&mut Switch)>(&mut self, value: Expr, build: F) {
+        let mut switch = Switch::new(value);
+        build(&mut switch);
+
+        self.push(Stmt::Switch(switch));
+    }
+
+    /// # Break out of the enclosing loop or switch.
+    ///
+    /// Rendering fails with [`Error::BreakOutsideLoop`](crate::Error::BreakOutsideLoop)
+    /// if there is no enclosing loop or switch.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{Code, Error, Expr};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.while_(Expr::Int32(1), |b| b.break_());
+    ///
+    /// assert!(code.render().is_ok());
+    ///
+    /// code.break_();
+    ///
+    /// assert_eq!(code.render(), Err(Error::BreakOutsideLoop));
+    /// ```
+    pub fn break_(&mut self) {
+        self.push(Stmt::Break);
+    }
+
+    /// # Continue with the next iteration of the enclosing loop.
+    ///
+    /// Rendering fails with [`Error::ContinueOutsideLoop`](crate::Error::ContinueOutsideLoop)
+    /// if there is no enclosing loop.
+    pub fn continue_(&mut self) {
+        self.push(Stmt::Continue);
+    }
+
+    /// # Add a conditional compilation region, built by `build`.
+    ///
+    /// Use the returned [`Conditional`] to add `#elif` and `#else` branches.
+    /// See [`Conditional`] for an example.
+    pub fn pp_if<F: FnOnce(&mut Block)>(
+        &mut self,
+        cond: PpCondition,
+        build: F,
+    ) -> &mut Conditional<Block> {
+        let mut then = Block::new();
+        build(&mut then);
+
+        self.push(Stmt::Conditional(Conditional::new(cond, then)));
+
+        match self.stmts.last_mut() {
+            Some(Stmt::Conditional(region)) => region,
+            _ => unreachable!(),
+        }
+    }
+
+    /// # Add a label to jump to with [`Block::goto`].
+    ///
+    /// Rendering fails with [`Error::DuplicateLabel`](crate::Error::DuplicateLabel)
+    /// if the function already has a label with this name.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{Code, Expr};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// code.if_(Expr::ident("failed"), |b| b.goto("out"));
+    /// code.call_func("work");
+    /// code.label("out");
+    /// code.call_func("done");
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// int main() {
+    /// if (failed) {
+    /// goto out;
+    /// }
+    /// work();
+    /// out:
+    /// done();
+    /// return 0;
+    /// }
+    /// "#.trim_start().to_string());
+    /// ```
+    pub fn label<S: Into<String>>(&mut self, name: S) {
+        self.push(Stmt::Label(name.into()));
+    }
+
+    /// # Jump to a label of the same function.
+    ///
+    /// Rendering fails with [`Error::UndefinedLabel`](crate::Error::UndefinedLabel)
+    /// if the function has no label with this name.
+    pub fn goto<S: Into<String>>(&mut self, name: S) {
+        self.push(Stmt::Goto(name.into()));
+    }
+
+    /// # Add a comment.
+    ///
+    /// See [`Comment`] for an example.
+    pub fn comment(&mut self, comment: Comment) {
+        self.push(Stmt::Comment(comment));
+    }
+
+    /// # Assert at compile time that `cond` holds.
+    ///
+    /// `cond` must be an integer constant expression. It is written as
+    /// `_Static_assert`, or `static_assert` in C23; rendering fails with
+    /// [`Error::Unsupported`](crate::Error::Unsupported) before C11.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use c_emit::{BinOp, Code, Expr, Standard};
+    ///
+    /// let mut code = Code::new();
+    ///
+    /// let size = Expr::call("sizeof", vec![Expr::ident("long")]);
+    /// code.static_assert(
+    ///     Expr::binary(BinOp::Ge, size, Expr::Int32(8)),
+    ///     "long must be 64-bit",
+    /// );
+    ///
+    /// assert_eq!(code.to_string(), r#"
+    /// int main() {
+    /// _Static_assert(sizeof(long) >= 8,"long must be 64-bit");
+    /// return 0;
+    /// }
+    /// "#.trim_start().to_string());
+    ///
+    /// code.standard(Standard::C23);
+    ///
+    /// assert!(code.to_string().contains("static_assert(sizeof(long) >= 8,"));
+    /// ```
+    pub fn static_assert<S: Into<String>>(&mut self, cond: Expr, message: S) {
+        self.push(Stmt::StaticAssert(cond, message.into()));
+    }
+
+    /// Visit every statement of this block and of the blocks nested in it.
+    pub(crate) fn visit<'a, F: FnMut(&'a Stmt)>(&'a self, f: &mut F) {
+        for stmt in &self.stmts {
+            f(stmt);
+            for block in stmt.blocks() {
+                block.visit(f);
+            }
+        }
+    }
+
+    /// Replace every return with an assignment to `result` and a jump to `label`.
+    ///
+    /// Without a `result`, a returned value is still evaluated for its side
+    /// effects, as `return f();` is in a `void` function.
+    pub(crate) fn rewrite_returns(&mut self, result: Option<&str>, label: &str) {
+        for mut stmt in std::mem::take(&mut self.stmts) {
+            if let Stmt::Return(value) = stmt {
+                match (result, value) {
+                    (Some(result), Some(value)) => self.assign(Expr::ident(result), value),
+                    (None, Some(value)) => self.push(Stmt::Expr(value)),
+                    (_, None) => {}
+                }
+                self.goto(label);
+                continue;
+            }
+
+            for block in stmt.blocks_mut() {
+                block.rewrite_returns(result, label);
+            }
+            self.push(stmt);
+        }
+    }
+
+    /// Collect what the statements in this block depend on.
+    pub(crate) fn requires(&self, out: &mut Requires) {
+        for stmt in &self.stmts {
+            match stmt {
+                Stmt::Expr(expr) | Stmt::Return(Some(expr)) | Stmt::StaticAssert(expr, _) => {
+                    expr.requires(out)
+                }
+                Stmt::Return(None) => {}
+                Stmt::If(stmt) => {
+                    for (cond, block) in &stmt.branches {
+                        cond.requires(out);
+                        block.requires(out);
+                    }
+                    if let Some(block) = &stmt.otherwise {
+                        block.requires(out);
+                    }
+                }
+                Stmt::While(cond, body) | Stmt::DoWhile(body, cond) => {
+                    cond.requires(out);
+                    body.requires(out);
+                }
+                Stmt::For(stmt) => {
+                    match &stmt.init {
+                        Some(ForInit::Decl(decl)) => decl.requires(out),
+                        Some(ForInit::Expr(expr)) => expr.requires(out),
+                        None => {}
+                    }
+                    for expr in stmt.cond.iter().chain(&stmt.step) {
+                        expr.requires(out);
+                    }
+                    stmt.body.requires(out);
+                }
+                Stmt::Switch(stmt) => {
+                    stmt.value.requires(out);
+                    for case in &stmt.cases {
+                        if let Some(label) = &case.label {
+                            label.requires(out);
+                        }
+                        case.body.requires(out);
+                    }
+                }
+                Stmt::Conditional(region) => {
+                    for block in region.branches() {
+                        block.requires(out);
+                    }
+                }
+                Stmt::Break
+                | Stmt::Continue
+                | Stmt::Label(_)
+                | Stmt::Goto(_)
+                | Stmt::Comment(_) => {}
+                Stmt::Decl(decl) => decl.requires(out),
+            }
+        }
+    }
+}
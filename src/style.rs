@@ -0,0 +1,119 @@
+//! # Output formatting.
+
+use crate::AssignOp;
+
+/// # How the C Code is laid out.
+///
+/// The default is the compact layout: no indentation, braces on the same
+/// line and no optional spaces.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{Braces, CArg, Code, Expr, Indent, Style, VarInit};
+///
+/// let mut code = Code::new();
+///
+/// code.style(Style {
+///     indent: Indent::Spaces(4),
+///     braces: Braces::Allman,
+///     space_after_comma: true,
+///     space_around_assign: true,
+///     ..Style::default()
+/// });
+/// code.new_var("n", VarInit::Int32(2));
+/// code.while_(Expr::ident("n"), |b| {
+///     b.call_func_with_args("printf", vec![CArg::String("%d\n"), CArg::Ident("n")]);
+///     b.decrement(Expr::ident("n"));
+/// });
+///
+/// assert_eq!(code.to_string(), r#"
+/// int main()
+/// {
+///     int n = 2;
+///     while (n)
+///     {
+///         printf("%d\n", n);
+///         n--;
+///     }
+///     return 0;
+/// }
+/// "#.trim_start().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Style {
+    /// The indentation of each nesting level.
+    pub indent: Indent,
+
+    /// Where the braces go.
+    pub braces: Braces,
+
+    /// Write `f(a, b)` instead of `f(a,b)`.
+    pub space_after_comma: bool,
+
+    /// Write `x = 1` instead of `x=1`, also for compound assignments.
+    pub space_around_assign: bool,
+
+    /// Write float and double literals in hexadecimal (`0x1.8p+1`), which
+    /// is exact, instead of in the shortest decimal that reads back exactly.
+    pub hex_floats: bool,
+}
+
+/// # The indentation of each nesting level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    /// A number of spaces, `0` writes everything flush-left.
+    Spaces(usize),
+
+    /// One tab.
+    Tabs,
+}
+
+/// # Where the braces go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Braces {
+    /// On the same line as the statement: `if (x) {` and `} else {`.
+    #[default]
+    KAndR,
+
+    /// On their own line, at the indentation of the statement.
+    Allman,
+
+    /// On their own line, half way between the statement and its body;
+    /// the braces of functions and types are not indented.
+    Gnu,
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Self::Spaces(0)
+    }
+}
+
+impl Style {
+    /// The separator between arguments, parameters and initializers.
+    pub(crate) fn comma(&self) -> &'static str {
+        if self.space_after_comma {
+            ", "
+        } else {
+            ","
+        }
+    }
+
+    /// An assignment operator, with the spaces around it.
+    pub(crate) fn assign(&self, op: AssignOp) -> String {
+        if self.space_around_assign {
+            format!(" {} ", op.symbol())
+        } else {
+            op.symbol().to_string()
+        }
+    }
+
+    /// The indentation of `depth` nesting levels.
+    pub(crate) fn indentation(&self, depth: usize) -> String {
+        match self.indent {
+            Indent::Spaces(width) => " ".repeat(width * depth),
+            Indent::Tabs => "\t".repeat(depth),
+        }
+    }
+}
@@ -0,0 +1,517 @@
+//! # User-defined C types.
+
+use crate::{CType, Comment, Requires, VarTypes};
+
+/// # A struct definition.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{Code, Struct, VarTypes};
+///
+/// let mut point = Struct::new("point");
+///
+/// point.field(VarTypes::Int32, "x");
+/// point.field(VarTypes::Int32, "y");
+///
+/// let mut code = Code::new();
+///
+/// code.add_struct(point);
+///
+/// assert_eq!(code.to_string(), r#"
+/// struct point {
+/// int x;
+/// int y;
+/// };
+/// int main() {
+/// return 0;
+/// }
+/// "#.trim_start().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Struct {
+    name: String,
+    fields: Vec<Field>,
+    comment: Option<Comment>,
+}
+
+/// # A field of a struct or union.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    /// The type of the field.
+    pub ty: CType,
+
+    /// The name of the field.
+    pub name: String,
+}
+
+impl Struct {
+    /// # Create a new struct without fields.
+    ///
+    /// Rendering fails with [`Error::EmptyRecord`](crate::Error::EmptyRecord)
+    /// if no field is added.
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            fields: vec![],
+            comment: None,
+        }
+    }
+
+    /// # Create a new struct without a name, for use in a [`Typedef`].
+    ///
+    /// Rendering fails with [`Error::AnonymousRecord`](crate::Error::AnonymousRecord)
+    /// if it is added on its own.
+    pub fn anonymous() -> Self {
+        Self::new("")
+    }
+
+    /// # Add a field to the struct.
+    ///
+    /// A `VarTypes::String` field is a `char *`, a string without a size
+    /// cannot be stored in place; see [`Struct::string_field`].
+    pub fn field<T: Into<CType>, S: Into<String>>(&mut self, ty: T, name: S) {
+        self.fields.push(Field {
+            ty: field_type(ty.into()),
+            name: name.into(),
+        });
+    }
+
+    /// # Add a fixed size string field to the struct: `char name[size]`.
+    pub fn string_field<S: Into<String>>(&mut self, name: S, size: usize) {
+        self.fields.push(Field {
+            ty: CType::Char.array(size),
+            name: name.into(),
+        });
+    }
+
+    /// # The name of the struct, empty if it is anonymous.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// # The fields of the struct.
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// # Attach a comment, written right before the struct.
+    pub fn set_comment(&mut self, comment: Comment) {
+        self.comment = Some(comment);
+    }
+
+    /// # The comment attached to the struct.
+    pub fn attached_comment(&self) -> Option<&Comment> {
+        self.comment.as_ref()
+    }
+
+    /// # The type to declare variables of this struct with.
+    pub fn ty(&self) -> VarTypes {
+        VarTypes::Struct(self.name.clone())
+    }
+
+    /// Collect what the struct depends on.
+    pub(crate) fn requires(&self, out: &mut Requires) {
+        fields_requires(&self.fields, out);
+    }
+}
+
+/// The type of a field: a string without a size is a pointer.
+fn field_type(ty: CType) -> CType {
+    if ty == CType::Char.unsized_array() {
+        ty.decay()
+    } else {
+        ty
+    }
+}
+
+/// # A union definition.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{Code, Union, VarTypes, VarInit};
+///
+/// let mut number = Union::new("number");
+///
+/// number.field(VarTypes::Int64, "i");
+/// number.field(VarTypes::Double, "d");
+///
+/// let mut code = Code::new();
+///
+/// code.new_var("n", VarInit::Uninit(number.ty()));
+/// code.add_union(number);
+///
+/// assert_eq!(code.to_string(), r#"
+/// #include<stdint.h>
+/// union number {
+/// int64_t i;
+/// double d;
+/// };
+/// int main() {
+/// union number n;
+/// return 0;
+/// }
+/// "#.trim_start().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Union {
+    name: String,
+    fields: Vec<Field>,
+    comment: Option<Comment>,
+}
+
+impl Union {
+    /// # Create a new union without fields.
+    ///
+    /// Rendering fails with [`Error::EmptyRecord`](crate::Error::EmptyRecord)
+    /// if no field is added.
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            fields: vec![],
+            comment: None,
+        }
+    }
+
+    /// # Create a new union without a name, for use in a [`Typedef`].
+    ///
+    /// Rendering fails with [`Error::AnonymousRecord`](crate::Error::AnonymousRecord)
+    /// if it is added on its own.
+    pub fn anonymous() -> Self {
+        Self::new("")
+    }
+
+    /// # Add a field to the union.
+    ///
+    /// A `VarTypes::String` field is a `char *`, a string without a size
+    /// cannot be stored in place; see [`Union::string_field`].
+    pub fn field<T: Into<CType>, S: Into<String>>(&mut self, ty: T, name: S) {
+        self.fields.push(Field {
+            ty: field_type(ty.into()),
+            name: name.into(),
+        });
+    }
+
+    /// # Add a fixed size string field to the union: `char name[size]`.
+    pub fn string_field<S: Into<String>>(&mut self, name: S, size: usize) {
+        self.fields.push(Field {
+            ty: CType::Char.array(size),
+            name: name.into(),
+        });
+    }
+
+    /// # The name of the union, empty if it is anonymous.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// # The fields of the union.
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// # Attach a comment, written right before the union.
+    pub fn set_comment(&mut self, comment: Comment) {
+        self.comment = Some(comment);
+    }
+
+    /// # The comment attached to the union.
+    pub fn attached_comment(&self) -> Option<&Comment> {
+        self.comment.as_ref()
+    }
+
+    /// # The type to declare variables of this union with.
+    pub fn ty(&self) -> VarTypes {
+        VarTypes::Union(self.name.clone())
+    }
+
+    /// Collect what the union depends on.
+    pub(crate) fn requires(&self, out: &mut Requires) {
+        fields_requires(&self.fields, out);
+    }
+}
+
+/// # A typedef.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{Code, Expr, Struct, Typedef, VarTypes, VarInit};
+///
+/// let mut vec2 = Struct::anonymous();
+///
+/// vec2.field(VarTypes::Float, "x");
+/// vec2.field(VarTypes::Float, "y");
+///
+/// let vec2 = Typedef::new("vec2", vec2);
+/// let score = Typedef::new("score", VarTypes::Int64);
+///
+/// let mut code = Code::new();
+///
+/// code.new_var("v", VarInit::Uninit(vec2.ty()));
+/// code.new_var("s", VarInit::Expr(score.ty(), Expr::Int64(0)));
+/// code.add_typedef(vec2);
+/// code.add_typedef(score);
+///
+/// assert_eq!(code.to_string(), r#"
+/// #include<stdint.h>
+/// typedef struct {
+/// float x;
+/// float y;
+/// } vec2;
+/// typedef int64_t score;
+/// int main() {
+/// vec2 v;
+/// score s=0LL;
+/// return 0;
+/// }
+/// "#.trim_start().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Typedef {
+    name: String,
+    target: TypedefTarget,
+    comment: Option<Comment>,
+}
+
+/// # The type a typedef names.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedefTarget {
+    /// An existing type.
+    Type(CType),
+
+    /// A struct, defined in place.
+    Struct(Struct),
+
+    /// A union, defined in place.
+    Union(Union),
+}
+
+impl From<VarTypes> for TypedefTarget {
+    fn from(ty: VarTypes) -> Self {
+        Self::Type(ty.into())
+    }
+}
+
+impl From<CType> for TypedefTarget {
+    fn from(ty: CType) -> Self {
+        Self::Type(ty)
+    }
+}
+
+impl From<Struct> for TypedefTarget {
+    fn from(def: Struct) -> Self {
+        Self::Struct(def)
+    }
+}
+
+impl From<Union> for TypedefTarget {
+    fn from(def: Union) -> Self {
+        Self::Union(def)
+    }
+}
+
+impl Typedef {
+    /// # Create a new typedef naming `target`.
+    pub fn new<S: Into<String>, T: Into<TypedefTarget>>(name: S, target: T) -> Self {
+        Self {
+            name: name.into(),
+            target: target.into(),
+            comment: None,
+        }
+    }
+
+    /// # The name the typedef introduces.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// # The type the typedef names.
+    pub fn target(&self) -> &TypedefTarget {
+        &self.target
+    }
+
+    /// # Attach a comment, written right before the typedef.
+    pub fn set_comment(&mut self, comment: Comment) {
+        self.comment = Some(comment);
+    }
+
+    /// # The comment attached to the typedef.
+    pub fn attached_comment(&self) -> Option<&Comment> {
+        self.comment.as_ref()
+    }
+
+    /// # The type to declare variables of this typedef with.
+    pub fn ty(&self) -> VarTypes {
+        VarTypes::Named(self.name.clone())
+    }
+
+    /// Collect what the typedef depends on.
+    pub(crate) fn requires(&self, out: &mut Requires) {
+        match &self.target {
+            TypedefTarget::Type(ty) => ty.requires(out),
+            TypedefTarget::Struct(def) => def.requires(out),
+            TypedefTarget::Union(def) => def.requires(out),
+        }
+    }
+}
+
+/// Collect what the fields of a struct or union depend on.
+fn fields_requires(fields: &[Field], out: &mut Requires) {
+    for field in fields {
+        field.ty.requires(out);
+    }
+}
+
+/// # An enum definition.
+///
+/// ## Example
+///
+/// ```rust
+/// use c_emit::{Code, Enum};
+///
+/// let mut color = Enum::new("color");
+///
+/// color.variant("RED");
+/// color.variant_with_value("GREEN", 4);
+/// color.variant("BLUE");
+/// color.generate_name_fn();
+///
+/// let mut code = Code::new();
+///
+/// code.add_enum(color);
+///
+/// assert_eq!(code.to_string(), r#"
+/// enum color {
+/// RED,
+/// GREEN=4,
+/// BLUE
+/// };
+/// const char *color_name(enum color v) {
+/// switch (v) {
+/// case RED:
+/// return "RED";
+/// case GREEN:
+/// return "GREEN";
+/// case BLUE:
+/// return "BLUE";
+/// }
+/// return "";
+/// }
+/// int main() {
+/// return 0;
+/// }
+/// "#.trim_start().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enum {
+    name: String,
+    variants: Vec<Variant>,
+    name_fn: bool,
+    comment: Option<Comment>,
+}
+
+/// # A variant of an enum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    /// The name of the variant.
+    pub name: String,
+
+    /// The explicit value of the variant, `None` is one more than the previous one.
+    ///
+    /// Values out of the range of `int` need C23.
+    pub value: Option<i64>,
+}
+
+impl Enum {
+    /// # Create a new enum without variants.
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            variants: vec![],
+            name_fn: false,
+            comment: None,
+        }
+    }
+
+    /// # Add a variant to the enum.
+    pub fn variant<S: Into<String>>(&mut self, name: S) {
+        self.variants.push(Variant {
+            name: name.into(),
+            value: None,
+        });
+    }
+
+    /// # Add a variant with an explicit value to the enum.
+    pub fn variant_with_value<S: Into<String>>(&mut self, name: S, value: i64) {
+        self.variants.push(Variant {
+            name: name.into(),
+            value: Some(value),
+        });
+    }
+
+    /// # Also generate a `const char *<name>_name(enum <name> v)` function.
+    ///
+    /// The function returns the name of a variant, or `""` for values that
+    /// are not variants. When variants share a value, the first one's name
+    /// is returned. In a [`Header`](crate::Header) the function is
+    /// `static inline` (`static` in C89), so every source including the
+    /// header gets its own copy instead of a duplicate definition.
+    ///
+    /// Rendering an enum without a name with the function fails with
+    /// [`Error::AnonymousEnumNameFn`](crate::Error::AnonymousEnumNameFn).
+    pub fn generate_name_fn(&mut self) {
+        self.name_fn = true;
+    }
+
+    /// # The name of the enum.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// # The variants of the enum.
+    pub fn variants(&self) -> &[Variant] {
+        &self.variants
+    }
+
+    /// # Whether the name function is generated.
+    pub fn has_name_fn(&self) -> bool {
+        self.name_fn
+    }
+
+    /// # Attach a comment, written right before the enum.
+    pub fn set_comment(&mut self, comment: Comment) {
+        self.comment = Some(comment);
+    }
+
+    /// # The comment attached to the enum.
+    pub fn attached_comment(&self) -> Option<&Comment> {
+        self.comment.as_ref()
+    }
+
+    /// # The type to declare variables of this enum with.
+    pub fn ty(&self) -> VarTypes {
+        VarTypes::Enum(self.name.clone())
+    }
+
+    /// The variants with a distinct value, in order.
+    pub(crate) fn distinct_variants(&self) -> Vec<&Variant> {
+        let mut seen = vec![];
+        let mut next = 0;
+
+        self.variants
+            .iter()
+            .filter(|variant| {
+                let value = variant.value.unwrap_or(next);
+                next = value.wrapping_add(1);
+
+                if seen.contains(&value) {
+                    return false;
+                }
+                seen.push(value);
+                true
+            })
+            .collect()
+    }
+}